@@ -23,10 +23,10 @@ use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
     compute_budget::ComputeBudgetInstruction,
     pubkey::{Pubkey},
-    instruction::{Instruction,AccountMeta},
+    instruction::{Instruction,AccountMeta,InstructionError},
     system_program
 };
 
@@ -42,6 +42,11 @@ use serde_json;
 use meteora::instruction::{
     MeteoraInstruction,
 };
+use meteora::error::MeteoraProxyError;
+use meteora::pda::Pda;
+use meteora::return_data::{ReturnData, SwapResult};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 // --- Constants ---
 const RPC_URL: &str = "https://api.devnet.solana.com";
@@ -137,6 +142,11 @@ fn main() -> Result<()> {
 
     let (event_authority_pda,_) = Pubkey::find_program_address(&[seeds::EVENT_AUTHORITY], &METEORA_PROGRAM_ID);
 
+    // proxy config and the fee vault of the swap input mint (token b for a SELL)
+    let (proxy_config_pda,_) = Pda::config(&PROGRAM_ID);
+    let proxy_fee_vault = Pda::fee_vault(&proxy_config_pda, &TOKEN_MINT_B, &spl_token_2022::ID);
+    let (allowed_pool_pda,_) = Pda::allowed_pool(&pool, &PROGRAM_ID);
+
 
     // PriceConfig::new(spl_price_usd, sol_price_usd, usd_value_to_provide, spl_decimal, sol_decimal)
     let (sqrt_price,liquidity) = PriceConfig::new(0.001, 180.0, 2.0, 6, 9).get_result();
@@ -200,6 +210,15 @@ fn main() -> Result<()> {
             // 14. meteora program
             AccountMeta::new_readonly(METEORA_PROGRAM_ID, false),
 
+            // 15. allowlist marker of the pool
+            AccountMeta::new_readonly(allowed_pool_pda, false),
+
+            // 16. proxy fee vault of the input mint
+            AccountMeta::new(proxy_fee_vault, false),
+
+            // 17. proxy config, last account of every instruction
+            AccountMeta::new_readonly(proxy_config_pda, false),
+
         ],
         data: params_swap.pack(),
     };
@@ -295,6 +314,9 @@ fn main() -> Result<()> {
 
             // 19. Event authority (must be readonly)
             AccountMeta::new_readonly(event_authority_pda, false),
+
+            // 20. proxy config, last account of every instruction
+            AccountMeta::new_readonly(proxy_config_pda, false),
         ],
         data: params_initialize_pool.pack(),
     };
@@ -311,8 +333,20 @@ fn main() -> Result<()> {
     tx_cpi_initialize_pool.sign(&[&payer, &position_nft_mint], recent_blockhash);
 
 
+    // The proxy publishes the realized swap amounts as return data
+    let simulation = client.simulate_transaction(&tx_cpi_swap)?;
+    if let Some(return_data) = simulation.value.return_data {
+        if let Some(result) = decode_return_data::<SwapResult>(&PROGRAM_ID, &return_data.program_id, &return_data.data.0) {
+            info!("Simulated swap result : {:?}", result);
+        }
+    }
+
     info!("Sending CPI transaction to create Meteora pool...");
-    let sig = client.send_and_confirm_transaction(&tx_cpi_swap)?;
+    let sig = client.send_and_confirm_transaction(&tx_cpi_swap).inspect_err(|e| {
+        if let Some(name) = proxy_error_name(e) {
+            error!("Proxy error: {}", name);
+        }
+    })?;
     info!("✅ CPI transaction successful!");
     info!("Transaction Signature: {}", sig);
     info!("🔍 View on Explorer: https://solscan.io/tx/{}?cluster=devnet", sig);
@@ -338,6 +372,35 @@ pub fn pool_get_mints_order(mint_a: Pubkey, mint_b: Pubkey) -> (Pubkey, Pubkey)
 
 
 
+// Decode the proxy return data of a simulated or confirmed transaction (base64 encoded)
+fn decode_return_data<T: ReturnData>(program_id: &Pubkey, return_program_id: &str, data_base64: &str) -> Option<T> {
+
+    if return_program_id != program_id.to_string() {
+        return None;
+    }
+
+    let data = STANDARD.decode(data_base64).ok()?;
+
+    T::decode(&data)
+}
+
+
+
+// Map a failed transaction back to the proxy error name, if the proxy raised it
+fn proxy_error_name(error: &solana_client::client_error::ClientError) -> Option<&'static str> {
+
+    match error.get_transaction_error()? {
+
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            MeteoraProxyError::from_code(code).map(MeteoraProxyError::name)
+        },
+
+        _ => None,
+    }
+}
+
+
+
 fn get_swap_params(direction: bool,amount_in: u64, slipage_bps: u64) -> MeteoraDammV2PoolSwapParams {
 
     let pool = get_pool();
@@ -0,0 +1,77 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::check::Check;
+use crate::error::MeteoraProxyError;
+use crate::pda::{Pda, TREASURY_PREFIX};
+use crate::token::TokenAccount;
+
+/// Who authorizes a Meteora CPI as the swap payer / position owner.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Authority {
+
+    // a transaction signer, forwarded with `invoke`
+    Signer,
+
+    // the program treasury PDA, forwarded with `invoke_signed`
+    Treasury,
+}
+
+impl Authority {
+
+    /// Validates the account acting as authority and returns how to sign the CPI.
+    pub fn check(self, program_id: &Pubkey, account: &AccountInfo) -> Result<CpiSigner, ProgramError> {
+        match self {
+            Authority::Signer => {
+                Check::check_is_signer(account)?;
+
+                Ok(CpiSigner { treasury_bump: None })
+            },
+
+            Authority::Treasury => {
+                let (treasury, bump) = Pda::treasury(program_id);
+                Check::check_pda(account, &treasury)?;
+
+                Ok(CpiSigner { treasury_bump: Some(bump) })
+            }
+        }
+    }
+
+    /// Checks that `account` may receive the tokens the CPI pays out.
+    ///
+    /// Treasury CPIs move protocol-owned funds, so they only pay into treasury token accounts.
+    pub fn check_destination(self, program_id: &Pubkey, account: &AccountInfo) -> Result<(), ProgramError> {
+        if self == Authority::Treasury {
+            let (treasury, _) = Pda::treasury(program_id);
+
+            if TokenAccount::owner(account)? != treasury {
+                msg!("Token account {} is not owned by the treasury", account.key);
+                return Err(MeteoraProxyError::InvalidTokenAccount.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CpiSigner {
+    treasury_bump: Option<u8>,
+}
+
+impl CpiSigner {
+
+    /// Invokes the CPI, signing for the treasury PDA when it is the authority.
+    pub fn invoke(&self, instruction: &Instruction, account_infos: &[AccountInfo]) -> ProgramResult {
+        match self.treasury_bump {
+            None => invoke(instruction, account_infos),
+            Some(bump) => invoke_signed(instruction, account_infos, &[&[TREASURY_PREFIX, &[bump]]]),
+        }
+    }
+}
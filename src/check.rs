@@ -2,12 +2,29 @@
 
 use solana_program::{
     account_info::AccountInfo,
+    bpf_loader_upgradeable,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
 };
 
+use crate::error::MeteoraProxyError;
+use crate::pda::{Pda, POOL_AUTHORITY};
+use crate::program_id::METEORA_PROGRAM_ID;
+use crate::meteora_v2_pool::{
+    TOKEN_A_MINT_BEGIN,
+    TOKEN_B_MINT_BEGIN,
+    TOKEN_B_VAULT_END,
+};
+
+// `ProgramData` of the upgradeable loader: u32 state tag, u64 deployment slot,
+// then the `Option<Pubkey>` upgrade authority
+const PROGRAM_DATA_STATE: [u8; 4] = [3, 0, 0, 0];
+const UPGRADE_AUTHORITY_OPTION: usize = 4 + 8;
+const UPGRADE_AUTHORITY_BEGIN: usize = UPGRADE_AUTHORITY_OPTION + 1;
+const UPGRADE_AUTHORITY_END: usize = UPGRADE_AUTHORITY_BEGIN + 32;
+
 pub struct Check;
 
 impl Check {
@@ -47,6 +64,134 @@ impl Check {
         Ok(())
     }
 
+    /// Checks if the given account is either the SPL Token or the SPL Token 2022 program.
+    pub fn check_any_token_program(account: &AccountInfo) -> ProgramResult {
+        if account.key != &spl_token::ID && account.key != &spl_token_2022::ID {
+            msg!("Incorrect token program ID: {}", account.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    /// Checks if the given account is the expected program derived address.
+    pub fn check_pda(account: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+        if account.key != expected {
+            msg!("Incorrect PDA: expected {}, got {}", expected, account.key);
+            return Err(MeteoraProxyError::InvalidPda.into());
+        }
+        Ok(())
+    }
+
+    /// Checks if the given account is the DAMM v2 program pinned for this cluster.
+    pub fn check_meteora_program(account: &AccountInfo) -> ProgramResult {
+        Self::check_program_id(account.key, &METEORA_PROGRAM_ID)
+            .map_err(|_| MeteoraProxyError::InvalidMeteoraProgram.into())
+    }
+
+    /// Checks if the given account is the constant Meteora pool authority.
+    pub fn check_pool_authority(account: &AccountInfo) -> ProgramResult {
+        if account.key != &POOL_AUTHORITY {
+            msg!("Incorrect pool authority: expected {}, got {}", POOL_AUTHORITY, account.key);
+            return Err(MeteoraProxyError::InvalidPoolAuthority.into());
+        }
+        Ok(())
+    }
+
+    /// Checks if the given account is the Meteora event authority PDA.
+    pub fn check_event_authority(account: &AccountInfo, meteora_program_id: &Pubkey) -> ProgramResult {
+        Self::check_pda(account, &Pda::event_authority(meteora_program_id))
+    }
+
+    /// Checks if the vaults are the `token_vault` PDAs of the given mints and pool.
+    pub fn check_token_vaults(
+        token_a_vault: &AccountInfo,
+        token_b_vault: &AccountInfo,
+        token_a_mint: &AccountInfo,
+        token_b_mint: &AccountInfo,
+        pool: &AccountInfo,
+        meteora_program_id: &Pubkey,
+    ) -> ProgramResult {
+        Self::check_pda(token_a_vault, &Pda::token_vault(token_a_mint.key, pool.key, meteora_program_id))?;
+        Self::check_pda(token_b_vault, &Pda::token_vault(token_b_mint.key, pool.key, meteora_program_id))?;
+        Ok(())
+    }
+
+    /// Checks if `pool` is a Meteora pool trading exactly these token A / token B mints.
+    pub fn check_pool_mints(
+        pool: &AccountInfo,
+        token_a_mint: &AccountInfo,
+        token_b_mint: &AccountInfo,
+        meteora_program_id: &Pubkey,
+    ) -> ProgramResult {
+        if pool.owner != meteora_program_id {
+            msg!("Pool {} is not owned by the Meteora program", pool.key);
+            return Err(MeteoraProxyError::InvalidPoolAccount.into());
+        }
+
+        let data = pool.try_borrow_data()?;
+        if data.len() < TOKEN_B_VAULT_END {
+            msg!("Pool {} data is too short", pool.key);
+            return Err(MeteoraProxyError::InvalidPoolAccount.into());
+        }
+
+        if data[TOKEN_A_MINT_BEGIN..TOKEN_A_MINT_BEGIN + 32] != token_a_mint.key.to_bytes()
+            || data[TOKEN_B_MINT_BEGIN..TOKEN_B_MINT_BEGIN + 32] != token_b_mint.key.to_bytes()
+        {
+            msg!("Token mints do not match pool {}", pool.key);
+            return Err(MeteoraProxyError::WrongMint.into());
+        }
+        Ok(())
+    }
+
+    /// Checks if `position` and `position_nft_account` are the PDAs of the NFT mint
+    /// recorded inside the position.
+    pub fn check_position_accounts(
+        position: &AccountInfo,
+        position_nft_account: &AccountInfo,
+        meteora_program_id: &Pubkey,
+    ) -> ProgramResult {
+        let position_nft_mint = Pda::position_nft_mint_from_data(&position.try_borrow_data()?)
+            .ok_or(MeteoraProxyError::InvalidPositionAccount)?;
+
+        Self::check_pda(position, &Pda::position(&position_nft_mint, meteora_program_id))?;
+        Self::check_pda(position_nft_account, &Pda::position_nft_account(&position_nft_mint, meteora_program_id))?;
+        Ok(())
+    }
+
+    /// Checks if `allowed_pool` is the allowlist marker of `pool` created by this program.
+    pub fn check_allowed_pool(program_id: &Pubkey, allowed_pool: &AccountInfo, pool: &AccountInfo) -> ProgramResult {
+        let (expected, _) = Pda::allowed_pool(pool.key, program_id);
+
+        if allowed_pool.key != &expected || allowed_pool.owner != program_id || allowed_pool.data_is_empty() {
+            msg!("Pool {} is not on the allowlist", pool.key);
+            return Err(MeteoraProxyError::PoolNotAllowed.into());
+        }
+        Ok(())
+    }
+
+    /// Checks if `authority` signed and is the upgrade authority of this program.
+    pub fn check_upgrade_authority(program_id: &Pubkey, program_data: &AccountInfo, authority: &AccountInfo) -> ProgramResult {
+        Self::check_is_signer(authority)?;
+
+        if program_data.key != &Pda::program_data(program_id) || program_data.owner != &bpf_loader_upgradeable::ID {
+            msg!("Account {} is not the program data of {}", program_data.key, program_id);
+            return Err(MeteoraProxyError::Unauthorized.into());
+        }
+
+        let data = program_data.try_borrow_data()?;
+        let upgrade_authority = if data.get(..4) == Some(&PROGRAM_DATA_STATE[..]) && data.get(UPGRADE_AUTHORITY_OPTION) == Some(&1) {
+            data.get(UPGRADE_AUTHORITY_BEGIN..UPGRADE_AUTHORITY_END)
+        } else {
+            None
+        };
+
+        if upgrade_authority != Some(authority.key.as_ref()) {
+            msg!("{} is not the upgrade authority of {}", authority.key, program_id);
+            return Err(MeteoraProxyError::Unauthorized.into());
+        }
+        Ok(())
+    }
+
     /// Checks if the instruction data is not empty.
     pub fn check_instr(instruction_data: &[u8]) -> ProgramResult {
         if instruction_data.is_empty() {
@@ -64,4 +209,108 @@ impl Check {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+
+    use super::*;
+
+    // ProgramData header: state tag, deployment slot, upgrade authority option
+    fn program_data(upgrade_authority: Option<&Pubkey>) -> Vec<u8> {
+        let mut data = PROGRAM_DATA_STATE.to_vec();
+        data.extend_from_slice(&42u64.to_le_bytes());
+
+        match upgrade_authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 33]),
+        }
+
+        // program bytes follow the header
+        data.extend_from_slice(&[7; 16]);
+        data
+    }
+
+    fn check(
+        program_id: &Pubkey,
+        program_data_key: &Pubkey,
+        program_data_owner: &Pubkey,
+        mut data: Vec<u8>,
+        authority: &Pubkey,
+        is_signer: bool,
+    ) -> ProgramResult {
+        let (mut data_lamports, mut authority_lamports) = (1, 1);
+        let mut authority_data = [];
+        let system_program = Pubkey::default();
+
+        let program_data = AccountInfo::new(program_data_key, false, false, &mut data_lamports, &mut data, program_data_owner, false, 0);
+        let authority = AccountInfo::new(authority, is_signer, false, &mut authority_lamports, &mut authority_data, &system_program, false, 0);
+
+        Check::check_upgrade_authority(program_id, &program_data, &authority)
+    }
+
+    #[test]
+    fn header_matches_the_loader_layout() {
+        assert_eq!(UPGRADE_AUTHORITY_END, UpgradeableLoaderState::size_of_programdata_metadata());
+    }
+
+    #[test]
+    fn upgrade_authority_signer_passes() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let key = Pda::program_data(&program_id);
+
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, program_data(Some(&authority)), &authority, true), Ok(()));
+    }
+
+    #[test]
+    fn other_signers_and_unsigned_authorities_fail() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let key = Pda::program_data(&program_id);
+        let unauthorized = Err(MeteoraProxyError::Unauthorized.into());
+
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, program_data(Some(&authority)), &Pubkey::new_unique(), true), unauthorized);
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, program_data(Some(&authority)), &authority, false), Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn immutable_and_malformed_program_data_fail() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let key = Pda::program_data(&program_id);
+        let unauthorized = Err(MeteoraProxyError::Unauthorized.into());
+
+        // no upgrade authority left
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, program_data(None), &authority, true), unauthorized);
+
+        // not a ProgramData state
+        let mut data = program_data(Some(&authority));
+        data[0] = 2;
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, data, &authority, true), unauthorized);
+
+        // truncated header
+        let mut data = program_data(Some(&authority));
+        data.truncate(UPGRADE_AUTHORITY_END - 1);
+        assert_eq!(check(&program_id, &key, &bpf_loader_upgradeable::ID, data, &authority, true), unauthorized);
+    }
+
+    #[test]
+    fn program_data_of_another_program_or_owner_fails() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let unauthorized = Err(MeteoraProxyError::Unauthorized.into());
+
+        let other_key = Pda::program_data(&Pubkey::new_unique());
+        assert_eq!(check(&program_id, &other_key, &bpf_loader_upgradeable::ID, program_data(Some(&authority)), &authority, true), unauthorized);
+
+        let key = Pda::program_data(&program_id);
+        assert_eq!(check(&program_id, &key, &Pubkey::new_unique(), program_data(Some(&authority)), &authority, true), unauthorized);
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use solana_program::program_error::ProgramError;
+
+// Codes start at 9000 so they never collide with SPL Token (0..) or
+// Anchor / Meteora (100..6999) errors that bubble up from a CPI.
+const ERROR_CODE_OFFSET: u32 = 9000;
+
+/// Errors returned by the proxy as `ProgramError::Custom(code)`.
+///
+/// The discriminants are part of the public interface: append new variants at the end
+/// and never reorder existing ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MeteoraProxyError {
+
+    // instruction data does not start with a known instruction tag
+    UnknownInstruction = ERROR_CODE_OFFSET,
+
+    // instruction tag is known but the payload does not match its layout
+    LayoutMismatch,
+
+    // an account does not match the address derived from its seeds
+    InvalidPda,
+
+    // pool authority is not the constant Meteora pool authority
+    InvalidPoolAuthority,
+
+    // meteora program account is not the DAMM v2 program pinned for this cluster
+    InvalidMeteoraProgram,
+
+    // the caller received less than the requested minimum
+    SlippageExceeded,
+
+    // a token mint does not match the one recorded in the pool
+    WrongMint,
+
+    // the proxy is paused by its admin
+    Paused,
+
+    // pool account is not owned by Meteora or is too short
+    InvalidPoolAccount,
+
+    // position account cannot be parsed
+    InvalidPositionAccount,
+
+    // token account is not an SPL Token / Token 2022 account
+    InvalidTokenAccount,
+
+    // config account is not a Meteora config
+    InvalidConfigAccount,
+
+    // signer is not the pool creator authority of the dynamic config
+    PoolCreatorAuthorityMismatch,
+
+    // reward index is not lower than `NUM_REWARDS`
+    InvalidRewardIndex,
+
+    // vesting parameters exceed the cp-amm limits
+    InvalidVestingParameters,
+
+    // lock liquidity amount is zero
+    InvalidLockLiquidity,
+
+    // customizable pool fee / price parameters are out of range
+    InvalidPoolParameters,
+
+    // slippage is above 10_000 basis points
+    InvalidSlippage,
+
+    // route has no hops, or a hop does not spend the output of the previous one
+    InvalidRoute,
+
+    // split swap has no legs, a zero leg, or legs that use different token accounts
+    InvalidSplit,
+
+    // account is not the initialized proxy config PDA
+    InvalidProxyConfig,
+
+    // signer is not the proxy admin
+    Unauthorized,
+
+    // proxy fee is above `MAX_PROXY_FEE_BPS`
+    InvalidFeeBps,
+
+    // fee vault is not the config token account of the mint
+    InvalidFeeVault,
+
+    // pool has no allowlist marker
+    PoolNotAllowed,
+
+    // pool charges a dynamic fee, which on-chain quotes do not model
+    DynamicFeeNotSupported,
+
+    // pool math overflowed while quoting a swap
+    MathOverflow,
+}
+
+impl MeteoraProxyError {
+
+    const ALL: [MeteoraProxyError; 27] = [
+        MeteoraProxyError::UnknownInstruction,
+        MeteoraProxyError::LayoutMismatch,
+        MeteoraProxyError::InvalidPda,
+        MeteoraProxyError::InvalidPoolAuthority,
+        MeteoraProxyError::InvalidMeteoraProgram,
+        MeteoraProxyError::SlippageExceeded,
+        MeteoraProxyError::WrongMint,
+        MeteoraProxyError::Paused,
+        MeteoraProxyError::InvalidPoolAccount,
+        MeteoraProxyError::InvalidPositionAccount,
+        MeteoraProxyError::InvalidTokenAccount,
+        MeteoraProxyError::InvalidConfigAccount,
+        MeteoraProxyError::PoolCreatorAuthorityMismatch,
+        MeteoraProxyError::InvalidRewardIndex,
+        MeteoraProxyError::InvalidVestingParameters,
+        MeteoraProxyError::InvalidLockLiquidity,
+        MeteoraProxyError::InvalidPoolParameters,
+        MeteoraProxyError::InvalidSlippage,
+        MeteoraProxyError::InvalidRoute,
+        MeteoraProxyError::InvalidSplit,
+        MeteoraProxyError::InvalidProxyConfig,
+        MeteoraProxyError::Unauthorized,
+        MeteoraProxyError::InvalidFeeBps,
+        MeteoraProxyError::InvalidFeeVault,
+        MeteoraProxyError::PoolNotAllowed,
+        MeteoraProxyError::DynamicFeeNotSupported,
+        MeteoraProxyError::MathOverflow,
+    ];
+
+    /// The custom error code carried by `ProgramError::Custom`.
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Maps a custom error code back to the proxy error, if it is one of ours.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|error| error.code() == code)
+    }
+
+    /// Client-side decoder: returns the proxy error behind a `ProgramError`, if any.
+    pub fn decode(error: &ProgramError) -> Option<Self> {
+        match error {
+            ProgramError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+
+    /// Stable variant name, e.g. for logs and client error messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            MeteoraProxyError::UnknownInstruction           => "UnknownInstruction",
+            MeteoraProxyError::LayoutMismatch               => "LayoutMismatch",
+            MeteoraProxyError::InvalidPda                   => "InvalidPda",
+            MeteoraProxyError::InvalidPoolAuthority         => "InvalidPoolAuthority",
+            MeteoraProxyError::InvalidMeteoraProgram        => "InvalidMeteoraProgram",
+            MeteoraProxyError::SlippageExceeded             => "SlippageExceeded",
+            MeteoraProxyError::WrongMint                    => "WrongMint",
+            MeteoraProxyError::Paused                       => "Paused",
+            MeteoraProxyError::InvalidPoolAccount           => "InvalidPoolAccount",
+            MeteoraProxyError::InvalidPositionAccount       => "InvalidPositionAccount",
+            MeteoraProxyError::InvalidTokenAccount          => "InvalidTokenAccount",
+            MeteoraProxyError::InvalidConfigAccount         => "InvalidConfigAccount",
+            MeteoraProxyError::PoolCreatorAuthorityMismatch => "PoolCreatorAuthorityMismatch",
+            MeteoraProxyError::InvalidRewardIndex           => "InvalidRewardIndex",
+            MeteoraProxyError::InvalidVestingParameters     => "InvalidVestingParameters",
+            MeteoraProxyError::InvalidLockLiquidity         => "InvalidLockLiquidity",
+            MeteoraProxyError::InvalidPoolParameters        => "InvalidPoolParameters",
+            MeteoraProxyError::InvalidSlippage              => "InvalidSlippage",
+            MeteoraProxyError::InvalidRoute                 => "InvalidRoute",
+            MeteoraProxyError::InvalidSplit                 => "InvalidSplit",
+            MeteoraProxyError::InvalidProxyConfig           => "InvalidProxyConfig",
+            MeteoraProxyError::Unauthorized                 => "Unauthorized",
+            MeteoraProxyError::InvalidFeeBps                => "InvalidFeeBps",
+            MeteoraProxyError::InvalidFeeVault              => "InvalidFeeVault",
+            MeteoraProxyError::PoolNotAllowed               => "PoolNotAllowed",
+            MeteoraProxyError::DynamicFeeNotSupported       => "DynamicFeeNotSupported",
+            MeteoraProxyError::MathOverflow                 => "MathOverflow",
+        }
+    }
+}
+
+impl fmt::Display for MeteoraProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.code())
+    }
+}
+
+impl From<MeteoraProxyError> for ProgramError {
+    fn from(error: MeteoraProxyError) -> Self {
+        ProgramError::Custom(error.code())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn codes_follow_the_declaration_order() {
+        for (index, error) in MeteoraProxyError::ALL.into_iter().enumerate() {
+            assert_eq!(error.code(), ERROR_CODE_OFFSET + index as u32);
+            assert_eq!(MeteoraProxyError::from_code(error.code()), Some(error));
+        }
+    }
+
+    #[test]
+    fn names_are_distinct() {
+        let mut names: Vec<&str> = MeteoraProxyError::ALL.iter().map(|error| error.name()).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        assert_eq!(names.len(), MeteoraProxyError::ALL.len());
+    }
+
+    #[test]
+    fn foreign_codes_are_not_decoded() {
+        let after_last = ERROR_CODE_OFFSET + MeteoraProxyError::ALL.len() as u32;
+
+        assert_eq!(MeteoraProxyError::from_code(after_last), None);
+        assert_eq!(MeteoraProxyError::decode(&ProgramError::Custom(6000)), None);
+        assert_eq!(
+            MeteoraProxyError::decode(&MeteoraProxyError::Paused.into()),
+            Some(MeteoraProxyError::Paused),
+        );
+    }
+}
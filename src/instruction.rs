@@ -1,11 +1,26 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::MeteoraProxyError;
+use crate::state::ConfigUpdate;
 
 use crate::meteora_v2_pool::{
     TradeDirection,
     InitializePoolParameters,
-    MeteoraDammV2PoolSwapParams
+    InitializeCustomizablePoolParameters,
+    MeteoraDammV2PoolSwapParams,
+    MeteoraDammV2PoolSwap2Params,
+    AddLiquidityParameters,
+    RemoveLiquidityParameters,
+    RemoveAllLiquidityParameters,
+    SplitPositionParameters,
+    VestingParameters,
+    InitialLock,
 };
 
+// number of `MeteoraInstruction` variants, bump it with every new variant
+const INSTRUCTION_COUNT: u8 = 35;
+
 /// The constant-product AMM instruction data.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum MeteoraInstruction {
@@ -13,6 +28,99 @@ pub enum MeteoraInstruction {
     // Meteora Damm V2 -> initialize_pool
     CpiInitializePool(InitializePoolParameters),
     CpiSwap(MeteoraDammV2PoolSwapParams,TradeDirection),
+
+    // Meteora Damm V2 -> add_liquidity
+    CpiAddLiquidity(AddLiquidityParameters),
+
+    // Meteora Damm V2 -> remove_liquidity
+    CpiRemoveLiquidity(RemoveLiquidityParameters),
+
+    // Meteora Damm V2 -> remove_all_liquidity
+    CpiRemoveAllLiquidity(RemoveAllLiquidityParameters),
+
+    // Meteora Damm V2 -> create_position
+    CpiCreatePosition,
+
+    // Meteora Damm V2 -> close_position
+    CpiClosePosition,
+
+    // Meteora Damm V2 -> split_position
+    CpiSplitPosition(SplitPositionParameters),
+
+    // Meteora Damm V2 -> claim_position_fee
+    CpiClaimPositionFee,
+
+    // Meteora Damm V2 -> claim_reward
+    CpiClaimReward { reward_index: u8 },
+
+    // Meteora Damm V2 -> lock_position
+    CpiLockPosition(VestingParameters),
+
+    // Meteora Damm V2 -> permanent_lock_position
+    CpiPermanentLockPosition { permanent_lock_liquidity: u128 },
+
+    // Meteora Damm V2 -> refresh_vesting
+    CpiRefreshVesting,
+
+    // Meteora Damm V2 -> initialize_customizable_pool
+    CpiInitializeCustomizablePool(InitializeCustomizablePoolParameters),
+
+    // Meteora Damm V2 -> initialize_pool_with_dynamic_config
+    CpiInitializePoolWithDynamicConfig(InitializeCustomizablePoolParameters),
+
+    // Meteora Damm V2 -> swap2
+    CpiSwap2(MeteoraDammV2PoolSwap2Params,TradeDirection),
+
+    // Same as the user variants, signed by the program treasury PDA on behalf of the config admin
+    CpiTreasurySwap(MeteoraDammV2PoolSwapParams,TradeDirection),
+    CpiTreasuryAddLiquidity(AddLiquidityParameters),
+    CpiTreasuryRemoveLiquidity(RemoveLiquidityParameters),
+    CpiTreasuryClaimPositionFee,
+
+    // Meteora Damm V2 -> swap, minimum_amount_out derived on-chain from the pool
+    CpiSwapWithSlippageBps { amount_in: u64, slippage_bps: u64, direction: TradeDirection },
+
+    // read-only quote of a Meteora Damm V2 swap, returned via return data
+    Quote { amount_in: u64, direction: TradeDirection },
+
+    // Meteora Damm V2 -> swap, chained across one pool per hop
+    CpiRouteSwap { amount_in: u64, minimum_final_out: u64, hops: Vec<TradeDirection> },
+
+    // Meteora Damm V2 -> swap, split across several pools of the same pair
+    CpiSplitSwap { legs: Vec<u64>, minimum_total_out: u64, direction: TradeDirection },
+
+    // Meteora Damm V2 -> initialize_pool, then swap in the new pool
+    CpiInitializePoolAndSwap(InitializePoolParameters,MeteoraDammV2PoolSwapParams,TradeDirection),
+
+    // Meteora Damm V2 -> initialize_pool, then permanent_lock_position or lock_position
+    CpiInitializePoolAndLock(InitializePoolParameters,InitialLock),
+
+    // Meteora Damm V2 -> swap part of a single token, then add_liquidity
+    CpiZapIn { amount_in: u64, input_is_a: bool, max_slippage_bps: u64 },
+
+    // Meteora Damm V2 -> remove_liquidity, then swap the unwanted side
+    CpiZapOut { liquidity_delta: u128, output_is_a: bool, minimum_out: u64 },
+
+    // proxy -> create the config PDA, signed by the upgrade authority, which becomes admin
+    InitializeConfig { fee_bps: u16, fee_recipient: Pubkey },
+
+    // proxy -> send the fee vault balance of one mint to the fee recipient
+    CollectProxyFees,
+
+    // proxy -> change pause switch, fee settings or allowed Meteora program
+    UpdateConfig(ConfigUpdate),
+
+    // proxy -> propose a new admin, who takes over with `AcceptAdmin`
+    TransferAdmin { new_admin: Pubkey },
+
+    // proxy -> create the allowlist marker of a pool, derived from its Meteora config and mints
+    AllowPool,
+
+    // proxy -> close the allowlist marker of a pool
+    DisallowPool,
+
+    // proxy -> the proposed admin takes over the config
+    AcceptAdmin,
 }
 
 impl MeteoraInstruction {
@@ -23,7 +131,95 @@ impl MeteoraInstruction {
     }
 
     // unpack
-    pub fn unpack(instruction_data: &[u8]) -> Result<Self, borsh::maybestd::io::Error> {
-        Self::try_from_slice(instruction_data)
+    pub fn unpack(instruction_data: &[u8]) -> Result<Self, ProgramError> {
+        // borsh encodes the variant as a leading u8 tag
+        match instruction_data.first() {
+            Some(tag) if *tag < INSTRUCTION_COUNT => {},
+            _ => {
+                msg!("Unknown instruction tag {:?}", instruction_data.first());
+                return Err(MeteoraProxyError::UnknownInstruction.into());
+            }
+        }
+
+        Self::try_from_slice(instruction_data).map_err(|error| {
+            msg!("Failed to unpack instruction: {}", error);
+            MeteoraProxyError::LayoutMismatch.into()
+        })
+    }
+
+    /// Admin instructions manage the config themselves and keep working while paused.
+    pub fn is_admin(&self) -> bool {
+        matches!(
+            self,
+            MeteoraInstruction::InitializeConfig { .. }
+                | MeteoraInstruction::CollectProxyFees
+                | MeteoraInstruction::UpdateConfig(_)
+                | MeteoraInstruction::TransferAdmin { .. }
+                | MeteoraInstruction::AcceptAdmin
+                | MeteoraInstruction::AllowPool
+                | MeteoraInstruction::DisallowPool
+        )
+    }
+
+    /// Read-only instructions move no funds and keep working while paused.
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, MeteoraInstruction::Quote { .. })
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn unpack_error(instruction_data: &[u8]) -> Option<ProgramError> {
+        MeteoraInstruction::unpack(instruction_data).err()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn last_variant_has_the_last_tag() {
+        assert_eq!(MeteoraInstruction::AcceptAdmin.pack(), vec![INSTRUCTION_COUNT - 1]);
+        assert!(matches!(
+            MeteoraInstruction::unpack(&[INSTRUCTION_COUNT - 1]),
+            Ok(MeteoraInstruction::AcceptAdmin),
+        ));
+    }
+
+    #[test]
+    fn unknown_tags_are_unknown_instructions() {
+        assert_eq!(unpack_error(&[INSTRUCTION_COUNT]), Some(MeteoraProxyError::UnknownInstruction.into()));
+        assert_eq!(unpack_error(&[u8::MAX, 1, 2, 3]), Some(MeteoraProxyError::UnknownInstruction.into()));
+        assert_eq!(unpack_error(&[]), Some(MeteoraProxyError::UnknownInstruction.into()));
+    }
+
+    #[test]
+    fn truncated_payloads_are_layout_mismatches() {
+        let mut instruction_data = MeteoraInstruction::CpiSwap(
+            MeteoraDammV2PoolSwapParams { amount_in: 1_000, minimum_amount_out: 900 },
+            TradeDirection::BUY,
+        ).pack();
+        instruction_data.pop();
+
+        assert_eq!(unpack_error(&instruction_data), Some(MeteoraProxyError::LayoutMismatch.into()));
+        assert_eq!(unpack_error(&[0]), Some(MeteoraProxyError::LayoutMismatch.into()));
+    }
+
+    #[test]
+    fn trailing_bytes_are_layout_mismatches() {
+        let mut instruction_data = MeteoraInstruction::CpiClaimPositionFee.pack();
+        instruction_data.push(0);
+
+        assert_eq!(unpack_error(&instruction_data), Some(MeteoraProxyError::LayoutMismatch.into()));
+    }
+
+    #[test]
+    fn only_quotes_are_read_only() {
+        let quote = MeteoraInstruction::Quote { amount_in: 1_000, direction: TradeDirection::SELL };
+
+        assert!(quote.is_read_only() && !quote.is_admin());
+        assert!(!MeteoraInstruction::CpiClaimPositionFee.is_read_only());
+        assert!(!MeteoraInstruction::AcceptAdmin.is_read_only());
+    }
+}
@@ -1,19 +1,62 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
 
+pub mod error;
+use crate::error::MeteoraProxyError;
+
 pub mod instruction;
 use crate::instruction::{MeteoraInstruction};
 
 pub mod check;
 use crate::check::Check;
 
+pub mod program_id;
+
+pub mod authority;
+use crate::authority::Authority;
+
+pub mod token;
+use crate::token::TokenAccount;
+
+pub mod pda;
+use crate::pda::{Pda, CONFIG_PREFIX, ALLOWED_POOL_PREFIX};
+
+pub mod state;
+use crate::state::{ProxyConfig, ConfigUpdate, AllowedPool};
+
+pub mod return_data;
+use crate::return_data::{ReturnData, SwapResult, LiquidityResult, RouteSwapResult, SplitSwapResult, ZapOutResult};
+
 pub mod meteora_v2_pool;
 pub use meteora_v2_pool::{
     
     TradeDirection,
     MeteoraDammV2Pool,
+    MeteoraDammV2PoolFee,
     MeteoraDammV2PoolSwapParams,
-    InitializePoolParameters
+    MeteoraDammV2PoolSwap2Params,
+    SwapMode,
+    InitializePoolParameters,
+    InitializeCustomizablePoolParameters,
+    AddLiquidityParameters,
+    RemoveLiquidityParameters,
+    RemoveAllLiquidityParameters,
+    SplitPositionParameters,
+    VestingParameters,
+    InitialLock,
+};
+use crate::meteora_v2_pool::{
+    NUM_REWARDS,
+    ACTIVATION_TYPE_BEGIN,
+    MAX_VESTING_SLOT_DURATION,
+    MAX_VESTING_TIME_DURATION,
+    CONFIG_DISCRIMINATOR,
+    CONFIG_POOL_CREATOR_AUTHORITY_BEGIN,
+    CONFIG_POOL_CREATOR_AUTHORITY_END,
+    quote,
+    zap_in_swap_amount,
+    liquidity_from_amount_a,
+    liquidity_from_amount_b,
 };
 
 use borsh::{BorshDeserialize,BorshSerialize};
@@ -24,9 +67,11 @@ use solana_program::{
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
     msg,
-    program::invoke,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
 entrypoint!(process_instruction);
@@ -38,6 +83,13 @@ fn process_instruction(
 ) -> ProgramResult {
     let instruction = MeteoraInstruction::unpack(instruction_data)?;
 
+    // Every instruction takes the proxy config as its last account.
+    let (config, accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    if !instruction.is_admin() && !instruction.is_read_only() {
+        ProxyConfig::load_active(program_id, config)?;
+    }
+
     match instruction {
 
         MeteoraInstruction::CpiInitializePool(
@@ -59,12 +111,404 @@ fn process_instruction(
             cpi_swap(
                 program_id,
                 accounts,
+                config,
                 MeteoraDammV2PoolSwapParams,
                 TradeDirection,
+                Authority::Signer,
             )?;
-        }
+        },
+
+        MeteoraInstruction::CpiAddLiquidity(
+            params,
+        ) => {
+            msg!("Instruction: CpiAddLiquidity");
+            cpi_add_liquidity(
+                program_id,
+                accounts,
+                params,
+                Authority::Signer,
+            )?;
+        },
+
+        MeteoraInstruction::CpiRemoveLiquidity(
+            params,
+        ) => {
+            msg!("Instruction: CpiRemoveLiquidity");
+            cpi_remove_liquidity(
+                program_id,
+                accounts,
+                params,
+                Authority::Signer,
+            )?;
+        },
+
+        MeteoraInstruction::CpiRemoveAllLiquidity(
+            params,
+        ) => {
+            msg!("Instruction: CpiRemoveAllLiquidity");
+            cpi_remove_all_liquidity(
+                program_id,
+                accounts,
+                params,
+                Authority::Signer,
+            )?;
+        },
+
+        MeteoraInstruction::CpiCreatePosition => {
+            msg!("Instruction: CpiCreatePosition");
+            cpi_create_position(
+                program_id,
+                accounts,
+            )?;
+        },
+
+        MeteoraInstruction::CpiClosePosition => {
+            msg!("Instruction: CpiClosePosition");
+            cpi_close_position(
+                program_id,
+                accounts,
+            )?;
+        },
+
+        MeteoraInstruction::CpiSplitPosition(
+            params,
+        ) => {
+            msg!("Instruction: CpiSplitPosition");
+            cpi_split_position(
+                program_id,
+                accounts,
+                params,
+            )?;
+        },
+
+        MeteoraInstruction::CpiClaimPositionFee => {
+            msg!("Instruction: CpiClaimPositionFee");
+            cpi_claim_position_fee(
+                program_id,
+                accounts,
+                Authority::Signer,
+            )?;
+        },
+
+        MeteoraInstruction::CpiClaimReward {
+            reward_index,
+        } => {
+            msg!("Instruction: CpiClaimReward");
+            cpi_claim_reward(
+                program_id,
+                accounts,
+                reward_index,
+            )?;
+        },
+
+        MeteoraInstruction::CpiLockPosition(
+            params,
+        ) => {
+            msg!("Instruction: CpiLockPosition");
+            cpi_lock_position(
+                program_id,
+                accounts,
+                params,
+            )?;
+        },
+
+        MeteoraInstruction::CpiPermanentLockPosition {
+            permanent_lock_liquidity,
+        } => {
+            msg!("Instruction: CpiPermanentLockPosition");
+            cpi_permanent_lock_position(
+                program_id,
+                accounts,
+                permanent_lock_liquidity,
+            )?;
+        },
+
+        MeteoraInstruction::CpiRefreshVesting => {
+            msg!("Instruction: CpiRefreshVesting");
+            cpi_refresh_vesting(
+                program_id,
+                accounts,
+            )?;
+        },
+
+        MeteoraInstruction::CpiInitializeCustomizablePool(
+            params,
+        ) => {
+            msg!("Instruction: CpiInitializeCustomizablePool");
+            cpi_initialize_customizable_pool(
+                program_id,
+                accounts,
+                params,
+            )?;
+        },
+
+        MeteoraInstruction::CpiInitializePoolWithDynamicConfig(
+            params,
+        ) => {
+            msg!("Instruction: CpiInitializePoolWithDynamicConfig");
+            cpi_initialize_pool_with_dynamic_config(
+                program_id,
+                accounts,
+                params,
+            )?;
+        },
+
+        MeteoraInstruction::CpiSwap2(
+            params,
+            direction,
+        ) => {
+            msg!("Instruction: CpiSwap2");
+            cpi_swap2(
+                program_id,
+                accounts,
+                config,
+                params,
+                direction,
+            )?;
+        },
+
+        MeteoraInstruction::CpiTreasurySwap(
+            params,
+            direction,
+        ) => {
+            msg!("Instruction: CpiTreasurySwap");
+            cpi_swap(
+                program_id,
+                treasury_accounts(program_id, accounts, config)?,
+                config,
+                params,
+                direction,
+                Authority::Treasury,
+            )?;
+        },
+
+        MeteoraInstruction::CpiTreasuryAddLiquidity(
+            params,
+        ) => {
+            msg!("Instruction: CpiTreasuryAddLiquidity");
+            cpi_add_liquidity(
+                program_id,
+                treasury_accounts(program_id, accounts, config)?,
+                params,
+                Authority::Treasury,
+            )?;
+        },
+
+        MeteoraInstruction::CpiTreasuryRemoveLiquidity(
+            params,
+        ) => {
+            msg!("Instruction: CpiTreasuryRemoveLiquidity");
+            cpi_remove_liquidity(
+                program_id,
+                treasury_accounts(program_id, accounts, config)?,
+                params,
+                Authority::Treasury,
+            )?;
+        },
+
+        MeteoraInstruction::CpiTreasuryClaimPositionFee => {
+            msg!("Instruction: CpiTreasuryClaimPositionFee");
+            cpi_claim_position_fee(
+                program_id,
+                treasury_accounts(program_id, accounts, config)?,
+                Authority::Treasury,
+            )?;
+        },
+
+        MeteoraInstruction::CpiSwapWithSlippageBps {
+            amount_in,
+            slippage_bps,
+            direction,
+        } => {
+            msg!("Instruction: CpiSwapWithSlippageBps");
+            cpi_swap_with_slippage_bps(
+                program_id,
+                accounts,
+                config,
+                amount_in,
+                slippage_bps,
+                direction,
+            )?;
+        },
+
+        MeteoraInstruction::Quote {
+            amount_in,
+            direction,
+        } => {
+            msg!("Instruction: Quote");
+            quote_swap(
+                accounts,
+                amount_in,
+                direction,
+            )?;
+        },
+
+        MeteoraInstruction::CpiRouteSwap {
+            amount_in,
+            minimum_final_out,
+            hops,
+        } => {
+            msg!("Instruction: CpiRouteSwap");
+            cpi_route_swap(
+                program_id,
+                accounts,
+                config,
+                amount_in,
+                minimum_final_out,
+                hops,
+            )?;
+        },
+
+        MeteoraInstruction::CpiSplitSwap {
+            legs,
+            minimum_total_out,
+            direction,
+        } => {
+            msg!("Instruction: CpiSplitSwap");
+            cpi_split_swap(
+                program_id,
+                accounts,
+                config,
+                legs,
+                minimum_total_out,
+                direction,
+            )?;
+        },
+
+        MeteoraInstruction::CpiInitializePoolAndSwap(
+            initialize_params,
+            swap_params,
+            direction,
+        ) => {
+            msg!("Instruction: CpiInitializePoolAndSwap");
+            cpi_initialize_pool_and_swap(
+                program_id,
+                accounts,
+                config,
+                initialize_params,
+                swap_params,
+                direction,
+            )?;
+        },
+
+        MeteoraInstruction::CpiInitializePoolAndLock(
+            initialize_params,
+            lock,
+        ) => {
+            msg!("Instruction: CpiInitializePoolAndLock");
+            cpi_initialize_pool_and_lock(
+                program_id,
+                accounts,
+                initialize_params,
+                lock,
+            )?;
+        },
+
+        MeteoraInstruction::CpiZapIn {
+            amount_in,
+            input_is_a,
+            max_slippage_bps,
+        } => {
+            msg!("Instruction: CpiZapIn");
+            cpi_zap_in(
+                program_id,
+                accounts,
+                config,
+                amount_in,
+                input_is_a,
+                max_slippage_bps,
+            )?;
+        },
+
+        MeteoraInstruction::CpiZapOut {
+            liquidity_delta,
+            output_is_a,
+            minimum_out,
+        } => {
+            msg!("Instruction: CpiZapOut");
+            cpi_zap_out(
+                program_id,
+                accounts,
+                config,
+                liquidity_delta,
+                output_is_a,
+                minimum_out,
+            )?;
+        },
+
+        MeteoraInstruction::InitializeConfig {
+            fee_bps,
+            fee_recipient,
+        } => {
+            msg!("Instruction: InitializeConfig");
+            initialize_config(
+                program_id,
+                accounts,
+                config,
+                fee_bps,
+                fee_recipient,
+            )?;
+        },
+
+        MeteoraInstruction::CollectProxyFees => {
+            msg!("Instruction: CollectProxyFees");
+            collect_proxy_fees(
+                program_id,
+                accounts,
+                config,
+            )?;
+        },
+
+        MeteoraInstruction::UpdateConfig(
+            update,
+        ) => {
+            msg!("Instruction: UpdateConfig");
+            update_config(
+                program_id,
+                accounts,
+                config,
+                update,
+            )?;
+        },
+
+        MeteoraInstruction::TransferAdmin {
+            new_admin,
+        } => {
+            msg!("Instruction: TransferAdmin");
+            transfer_admin(
+                program_id,
+                accounts,
+                config,
+                new_admin,
+            )?;
+        },
+
+        MeteoraInstruction::AllowPool => {
+            msg!("Instruction: AllowPool");
+            allow_pool(
+                program_id,
+                accounts,
+                config,
+            )?;
+        },
+
+        MeteoraInstruction::DisallowPool => {
+            msg!("Instruction: DisallowPool");
+            disallow_pool(
+                program_id,
+                accounts,
+                config,
+            )?;
+        },
 
-        _ => {}
+        MeteoraInstruction::AcceptAdmin => {
+            msg!("Instruction: AcceptAdmin");
+            accept_admin(
+                program_id,
+                accounts,
+                config,
+            )?;
+        }
     }
 
     Ok(())
@@ -108,10 +552,20 @@ fn cpi_initialize_pool(
 
 
     // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
     Check::check_is_signer(payer)?;
     Check::check_is_signer(position_nft_mint)?;
     Check::check_system_program(system_program)?;
 
+    // --- PDA Checks ---
+    // Re-derive every Meteora account so a wrong one fails here with a clear message.
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_pda(pool, &Pda::pool(config.key, token_a_mint.key, token_b_mint.key, meteora_program.key))?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_pda(position, &Pda::position(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pda(position_nft_account, &Pda::position_nft_account(position_nft_mint.key, meteora_program.key))?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
     // --- Construct CPI Instruction Data ---
     // This struct must match the `InitializePoolParameters` expected by Meteora.
     #[derive(BorshSerialize,BorshDeserialize)]
@@ -197,90 +651,2451 @@ fn cpi_initialize_pool(
 
 
 
-#[allow(clippy::too_many_arguments)]
-fn cpi_swap(
-    _program_id: &Pubkey,
+/// Calls the Meteora DAMM `initialize_pool` instruction, then `swap` in the new pool.
+///
+/// Both CPIs run in the same instruction, so the creator's first buy lands before anyone
+/// else can trade. Takes the `cpi_initialize_pool` accounts followed by the referral
+/// token account, the allowlist marker of the new pool, which the admin creates ahead
+/// of the launch, and the fee vault of the input mint; `minimum_amount_out` guards the
+/// swap and the proxy fee is skimmed like in `cpi_swap`.
+fn cpi_initialize_pool_and_swap(
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    MeteoraDammV2PoolSwapParams: MeteoraDammV2PoolSwapParams,
-    TradeDirection: TradeDirection,
+    config: &AccountInfo,
+    initialize_params: InitializePoolParameters,
+    swap_params: MeteoraDammV2PoolSwapParams,
+    direction: TradeDirection,
 ) -> ProgramResult {
 
-    msg!("Instruction to swap");
-    msg!("amount in : {}", MeteoraDammV2PoolSwapParams.amount_in);
-    msg!("minimum_amount_out : {}", MeteoraDammV2PoolSwapParams.minimum_amount_out);
-    
     let accounts_iter = &mut accounts.iter();
 
     // The order of accounts must match the client-side order exactly.
     let payer                       = next_account_info(accounts_iter)?;
+    let _creator                    = next_account_info(accounts_iter)?;
+    let _position_nft_mint          = next_account_info(accounts_iter)?;
+    let _position_nft_account       = next_account_info(accounts_iter)?;
+    let _config                     = next_account_info(accounts_iter)?;
     let pool_authority              = next_account_info(accounts_iter)?;
     let pool                        = next_account_info(accounts_iter)?;
+    let _position                   = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let payer_token_a               = next_account_info(accounts_iter)?;
+    let payer_token_b               = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let _token_2022_program         = next_account_info(accounts_iter)?;
+    let _system_program             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let referral_token_account      = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+    let fee_vault                   = next_account_info(accounts_iter)?;
 
-    let (
-        input_token_account,
-        output_token_account,
-
-    ) = match TradeDirection {
+    cpi_initialize_pool(
+        program_id,
+        accounts,
+        initialize_params,
+    )?;
 
-        TradeDirection::BUY => {
+    msg!("amount in : {}", swap_params.amount_in);
+    msg!("minimum_amount_out : {}", swap_params.minimum_amount_out);
 
-            let input_token_account         = next_account_info(accounts_iter)?;
-            let output_token_account        = next_account_info(accounts_iter)?;
-            
-            (input_token_account,output_token_account)
-        },
+    let swap_accounts = SwapAccounts {
+        payer,
+        pool_authority,
+        pool,
+        token_a_account: payer_token_a,
+        token_b_account: payer_token_b,
+        token_a_vault,
+        token_b_vault,
+        token_a_mint,
+        token_b_mint,
+        token_a_program,
+        token_b_program,
+        referral_token_account,
+        event_authority,
+        meteora_program,
+        allowed_pool,
+    }.group();
 
-        TradeDirection::SELL => {
+    let result = invoke_swap_with_proxy_fee(
+        program_id,
+        &swap_accounts,
+        config,
+        fee_vault,
+        swap_params,
+        direction,
+    )?;
 
-            let input_token_account         = next_account_info(accounts_iter)?;
-            let output_token_account        = next_account_info(accounts_iter)?;
-            
-            (output_token_account,input_token_account)
-        }
-    };
+    result.publish();
 
-    
-    let token_a_vault               = next_account_info(accounts_iter)?;
-    let token_b_vault               = next_account_info(accounts_iter)?;
-    let token_a_mint                = next_account_info(accounts_iter)?;
-    let token_b_mint                = next_account_info(accounts_iter)?;
-    let token_a_program             = next_account_info(accounts_iter)?;
-    let token_b_program             = next_account_info(accounts_iter)?;    
-    let referral_token_account      = next_account_info(accounts_iter)?;
-    let event_authority             = next_account_info(accounts_iter)?;
-    let meteora_program             = next_account_info(accounts_iter)?;
-    
-    
+    Ok(())
+}
 
-    // --- Validation Checks ---
-    Check::check_is_signer(payer)?;
 
 
-    let params = MeteoraDammV2PoolSwapParams;
+/// Calls the Meteora DAMM `initialize_pool` instruction, then locks the new position.
+///
+/// `InitialLock::Permanent` locks all of the initial liquidity with `permanent_lock_position`,
+/// `InitialLock::Vesting` moves it into a vesting schedule with `lock_position`. Both CPIs
+/// run in the same instruction, so the liquidity is never unlocked on-chain. Takes the
+/// `cpi_initialize_pool` accounts, followed by a fresh `vesting` keypair for vesting locks.
+/// The creator owns the position and must sign.
+fn cpi_initialize_pool_and_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initialize_params: InitializePoolParameters,
+    lock: InitialLock,
+) -> ProgramResult {
 
-    // The instruction discriminator for `swap` 
-    // 需要通过测试或IDL确定，这里使用占位符
-    let mut instruction_data_cpi = vec![248,198,158,145,225,117,135,200]; 
-    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+    let accounts_iter = &mut accounts.iter();
 
-    // --- Construct CPI Accounts ---
-    // The order must match Meteora's `swap` instruction.
-    let account_metas = vec![
-        AccountMeta::new_readonly(*pool_authority.key, false),
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let creator                     = next_account_info(accounts_iter)?;
+    let _position_nft_mint          = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let _config                     = next_account_info(accounts_iter)?;
+    let _pool_authority             = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let _token_a_mint               = next_account_info(accounts_iter)?;
+    let _token_b_mint               = next_account_info(accounts_iter)?;
+    let _token_a_vault              = next_account_info(accounts_iter)?;
+    let _token_b_vault              = next_account_info(accounts_iter)?;
+    let _payer_token_a              = next_account_info(accounts_iter)?;
+    let _payer_token_b              = next_account_info(accounts_iter)?;
+    let _token_a_program            = next_account_info(accounts_iter)?;
+    let _token_b_program            = next_account_info(accounts_iter)?;
+    let _token_2022_program         = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+
+    let initial_liquidity = initialize_params.liquidity;
+
+    cpi_initialize_pool(
+        program_id,
+        accounts,
+        initialize_params,
+    )?;
+
+    match lock {
+
+        InitialLock::Permanent => {
+
+            // The order must match the `cpi_permanent_lock_position` accounts.
+            let lock_accounts = [
+                creator.clone(),
+                pool.clone(),
+                position.clone(),
+                position_nft_account.clone(),
+                event_authority.clone(),
+                meteora_program.clone(),
+            ];
+
+            cpi_permanent_lock_position(
+                program_id,
+                &lock_accounts,
+                initial_liquidity,
+            )
+        },
+
+        InitialLock::Vesting(params) => {
+
+            let vesting = next_account_info(accounts_iter)?;
+
+            // The order must match the `cpi_lock_position` accounts.
+            let lock_accounts = [
+                payer.clone(),
+                creator.clone(),
+                vesting.clone(),
+                pool.clone(),
+                position.clone(),
+                position_nft_account.clone(),
+                system_program.clone(),
+                event_authority.clone(),
+                meteora_program.clone(),
+            ];
+
+            cpi_lock_position(
+                program_id,
+                &lock_accounts,
+                params,
+            )
+        }
+    }
+}
+
+
+
+#[allow(clippy::too_many_arguments)]
+fn cpi_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    MeteoraDammV2PoolSwapParams: MeteoraDammV2PoolSwapParams,
+    TradeDirection: TradeDirection,
+    authority: Authority,
+) -> ProgramResult {
+
+    msg!("Instruction to swap");
+    msg!("amount in : {}", MeteoraDammV2PoolSwapParams.amount_in);
+    msg!("minimum_amount_out : {}", MeteoraDammV2PoolSwapParams.minimum_amount_out);
+
+    let params = MeteoraDammV2PoolSwapParams;
+
+    let result = match authority {
+
+        Authority::Signer => {
+            let fee_vault = accounts.get(SWAP_ACCOUNTS_LEN).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            invoke_swap_with_proxy_fee(program_id, accounts, config, fee_vault, params, TradeDirection)?
+        },
+
+        // Treasury swaps move protocol-owned funds and pay no proxy fee.
+        Authority::Treasury => {
+            let mut instruction_data_cpi = SWAP_DISCRIMINATOR.to_vec();
+            instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+            invoke_swap(
+                program_id,
+                accounts,
+                TradeDirection,
+                instruction_data_cpi,
+                params.minimum_amount_out,
+                authority,
+            )?
+        },
+    };
+
+    result.publish();
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `swap` instruction with a minimum out quoted at execution time.
+///
+/// Reads liquidity, sqrt price and fee from the `pool` account and applies the slippage to
+/// the fee-aware `quote`, so the price cannot move between quoting and landing. The quote
+/// is taken on the amount left after the proxy fee. Takes the same accounts as `cpi_swap`.
+fn cpi_swap_with_slippage_bps(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    amount_in: u64,
+    slippage_bps: u64,
+    direction: TradeDirection,
+) -> ProgramResult {
+
+    if slippage_bps > 10_000 {
+        msg!("Slippage {} bps is above 10000", slippage_bps);
+        return Err(MeteoraProxyError::InvalidSlippage.into());
+    }
+
+    // payer, pool authority, then the pool
+    let pool = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let pool_state = read_pool(pool)?;
+    let pool_fee = read_pool_fee(pool)?;
+
+    msg!("pool liquidity : {}", pool_state.liquidity);
+    msg!("pool sqrt price : {}", pool_state.sqrt_price);
+
+    let proxy_fee = ProxyConfig::load(program_id, config)?.fee_on(amount_in);
+    let expected_out = quote(&pool_state, &pool_fee, direction.is_a_to_b(), amount_in - proxy_fee)
+        .ok_or(MeteoraProxyError::MathOverflow)?
+        .amount_out;
+
+    // `cpi_swap` skims the fee from the full amount
+    let params = MeteoraDammV2PoolSwapParams {
+        amount_in,
+        minimum_amount_out: expected_out.saturating_mul(10_000 - slippage_bps) / 10_000,
+    };
+
+    cpi_swap(
+        program_id,
+        accounts,
+        config,
+        params,
+        direction,
+        Authority::Signer,
+    )
+}
+
+
+
+/// Chains Meteora DAMM `swap` instructions across several pools, e.g. X -> SOL -> USDC.
+///
+/// `accounts` holds one `cpi_swap` account group per hop, in route order, followed by the
+/// fee vault of the first input mint. The proxy fee is skimmed once
+/// from `amount_in`. Every hop spends exactly what the previous hop delivered, measured on
+/// the token balances, and only the last hop enforces `minimum_final_out`.
+fn cpi_route_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    amount_in: u64,
+    minimum_final_out: u64,
+    hops: Vec<TradeDirection>,
+) -> ProgramResult {
+
+    msg!("Instruction to route swap");
+    msg!("amount in : {}", amount_in);
+    msg!("minimum final out : {}", minimum_final_out);
+    msg!("hops : {}", hops.len());
+
+    // --- Validation Checks ---
+    if hops.is_empty() || accounts.len() != hops.len() * SWAP_ACCOUNTS_LEN + 1 {
+        msg!("Expected {} swap account groups and the fee vault, got {} accounts", hops.len(), accounts.len());
+        return Err(MeteoraProxyError::InvalidRoute.into());
+    }
+
+    let (fee_vault, swap_accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let groups: Vec<&[AccountInfo]> = swap_accounts.chunks_exact(SWAP_ACCOUNTS_LEN).collect();
+
+    for (index, pair) in groups.windows(2).enumerate() {
+        let (_, previous_output) = swap_token_accounts(pair[0], hops[index]);
+        let (next_input, _) = swap_token_accounts(pair[1], hops[index + 1]);
+
+        if previous_output.key != next_input.key {
+            msg!("Hop {} does not spend the output {} of the previous hop", index + 1, previous_output.key);
+            return Err(MeteoraProxyError::InvalidRoute.into());
+        }
+    }
+
+    let proxy_fee = skim_proxy_fee(program_id, groups[0], config, fee_vault, hops[0], amount_in)?;
+
+    let hop_count = hops.len();
+    let mut results: Vec<SwapResult> = Vec::with_capacity(hop_count);
+    let mut hop_amount_in = amount_in - proxy_fee;
+
+    for (index, (group, direction)) in groups.iter().zip(hops).enumerate() {
+
+        let last_hop = index + 1 == hop_count;
+
+        let params = MeteoraDammV2PoolSwapParams {
+            amount_in: hop_amount_in,
+            minimum_amount_out: if last_hop { minimum_final_out } else { 0 },
+        };
+
+        msg!("hop {} amount in : {}", index, params.amount_in);
+
+        let mut instruction_data_cpi = SWAP_DISCRIMINATOR.to_vec();
+        instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+        let result = invoke_swap(
+            program_id,
+            group,
+            direction,
+            instruction_data_cpi,
+            params.minimum_amount_out,
+            Authority::Signer,
+        )?;
+
+        hop_amount_in = result.amount_out;
+        results.push(result);
+    }
+
+    let route_result = RouteSwapResult {
+        amount_in: results[0].amount_in + proxy_fee,
+        amount_out: hop_amount_in,
+        hops: results,
+    };
+
+    msg!("Route swap executed successfully via CPI");
+
+    route_result.publish();
+
+    Ok(())
+}
+
+
+
+/// Splits one swap into Meteora DAMM `swap` instructions on several pools of the same pair.
+///
+/// `accounts` holds one `cpi_swap` account group per leg, all with the same input and
+/// output token accounts, followed by the fee vault of the input mint. `legs` are the
+/// amounts in per pool, e.g. from `optimal_split`; each leg pays the proxy fee on its
+/// amount. Legs do not enforce a minimum of their own: `minimum_total_out` is enforced
+/// on the sum of the measured outputs.
+fn cpi_split_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    legs: Vec<u64>,
+    minimum_total_out: u64,
+    direction: TradeDirection,
+) -> ProgramResult {
+
+    msg!("Instruction to split swap");
+    msg!("legs : {}", legs.len());
+    msg!("minimum total out : {}", minimum_total_out);
+
+    // --- Validation Checks ---
+    if legs.is_empty() || accounts.len() != legs.len() * SWAP_ACCOUNTS_LEN + 1 {
+        msg!("Expected {} swap account groups and the fee vault, got {} accounts", legs.len(), accounts.len());
+        return Err(MeteoraProxyError::InvalidSplit.into());
+    }
+
+    if legs.contains(&0) {
+        msg!("Split swap legs must have a non-zero amount in");
+        return Err(MeteoraProxyError::InvalidSplit.into());
+    }
+
+    let (fee_vault, swap_accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let groups: Vec<&[AccountInfo]> = swap_accounts.chunks_exact(SWAP_ACCOUNTS_LEN).collect();
+
+    let (input_token_account, output_token_account) = swap_token_accounts(groups[0], direction);
+
+    for group in groups.iter().skip(1) {
+        let (input, output) = swap_token_accounts(group, direction);
+
+        if input.key != input_token_account.key || output.key != output_token_account.key {
+            msg!("All legs must swap between {} and {}", input_token_account.key, output_token_account.key);
+            return Err(MeteoraProxyError::InvalidSplit.into());
+        }
+    }
+
+    let mut results: Vec<SwapResult> = Vec::with_capacity(legs.len());
+
+    for (index, (group, leg_amount_in)) in groups.iter().zip(legs).enumerate() {
+
+        let params = MeteoraDammV2PoolSwapParams {
+            amount_in: leg_amount_in,
+            minimum_amount_out: 0,
+        };
+
+        msg!("leg {} amount in : {}", index, params.amount_in);
+
+        let result = invoke_swap_with_proxy_fee(
+            program_id,
+            group,
+            config,
+            fee_vault,
+            params,
+            direction,
+        )?;
+
+        results.push(result);
+    }
+
+    // --- Enforce Minimum Out ---
+    let split_result = SplitSwapResult {
+        amount_in: results.iter().map(|result| result.amount_in).sum(),
+        amount_out: results.iter().map(|result| result.amount_out).sum(),
+        legs: results,
+    };
+
+    msg!("total amount out : {}", split_result.amount_out);
+
+    if split_result.amount_out < minimum_total_out {
+        msg!("Received {} is below the minimum total out {}", split_result.amount_out, minimum_total_out);
+        return Err(MeteoraProxyError::SlippageExceeded.into());
+    }
+
+    msg!("Split swap executed successfully via CPI");
+
+    split_result.publish();
+
+    Ok(())
+}
+
+
+
+/// Quotes a Meteora DAMM `swap` without executing it.
+///
+/// Only reads the `pool` account and publishes a `QuoteResult`, so other programs can
+/// call it through CPI and clients through `simulateTransaction`. The trailing config
+/// account is not loaded: quotes keep working while the proxy is paused.
+fn quote_swap(
+    accounts: &[AccountInfo],
+    amount_in: u64,
+    direction: TradeDirection,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let pool = next_account_info(accounts_iter)?;
+
+    // --- Validation Checks ---
+    if pool.owner != &program_id::METEORA_PROGRAM_ID {
+        msg!("Pool {} is not owned by the Meteora program", pool.key);
+        return Err(MeteoraProxyError::InvalidPoolAccount.into());
+    }
+
+    let pool_state = read_pool(pool)?;
+    let pool_fee = read_pool_fee(pool)?;
+
+    let result = quote(&pool_state, &pool_fee, direction.is_a_to_b(), amount_in).ok_or(MeteoraProxyError::MathOverflow)?;
+
+    msg!("quote amount out : {}", result.amount_out);
+    msg!("quote fee : {}", result.fee);
+    msg!("quote price impact bps : {}", result.price_impact_bps);
+
+    result.publish();
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `swap2` instruction.
+///
+/// `swap2` adds partial fill and exact out on top of `swap`. For `ExactOut`, `amount_0`
+/// is the amount to buy and `amount_1` the maximum amount the payer is willing to pay;
+/// both are enforced on the measured balances, like the minimum out of the other modes.
+/// Takes the `cpi_swap` accounts. Exact in skims the proxy fee from `amount_0`. Partial
+/// fill charges it on the measured input, within `amount_0`, and exact out on top of the
+/// measured input, within `amount_1`.
+fn cpi_swap2(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    mut params: MeteoraDammV2PoolSwap2Params,
+    direction: TradeDirection,
+) -> ProgramResult {
+
+    msg!("swap mode : {:?}", params.swap_mode);
+    msg!("amount 0 : {}", params.amount_0);
+    msg!("amount 1 : {}", params.amount_1);
+
+    let fee_vault = accounts.get(SWAP_ACCOUNTS_LEN).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let (minimum_amount_out, maximum_amount_in, upfront_fee) = match params.swap_mode {
+        SwapMode::ExactIn => {
+            let proxy_fee = skim_proxy_fee(program_id, accounts, config, fee_vault, direction, params.amount_0)?;
+            params.amount_0 -= proxy_fee;
+
+            (params.amount_1, u64::MAX, proxy_fee)
+        },
+
+        // Meteora may fill less: leave room for the fee now, charge it on what was swapped.
+        SwapMode::PartialFill => {
+            let maximum_amount_in = params.amount_0;
+            params.amount_0 -= ProxyConfig::load(program_id, config)?.fee_on(params.amount_0);
+
+            (params.amount_1, maximum_amount_in, 0)
+        },
+
+        SwapMode::ExactOut => (params.amount_0, params.amount_1, 0),
+    };
+
+    // The instruction discriminator for `swap2`
+    let mut instruction_data_cpi = vec![65,75,63,76,235,91,91,136];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    let mut result = invoke_swap(
+        program_id,
+        accounts,
+        direction,
+        instruction_data_cpi,
+        minimum_amount_out,
+        Authority::Signer,
+    )?;
+
+    // Partial fill and exact out only know their input once the swap ran.
+    result.amount_in += match params.swap_mode {
+        SwapMode::ExactIn => upfront_fee,
+        SwapMode::PartialFill | SwapMode::ExactOut => {
+            skim_proxy_fee(program_id, accounts, config, fee_vault, direction, result.amount_in)?
+        },
+    };
+
+    // Token-2022 transfer fees and the proxy fee are charged on top of what Meteora checks.
+    if result.amount_in > maximum_amount_in {
+        msg!("Spent {} is above the maximum amount in {}", result.amount_in, maximum_amount_in);
+        return Err(MeteoraProxyError::SlippageExceeded.into());
+    }
+
+    result.publish();
+
+    Ok(())
+}
+
+
+
+/// Skims the proxy fee on `amount_in` from the swap input into the fee vault.
+///
+/// `fee_vault` is the config token account of the input mint. The swap payer signs the
+/// transfer. Returns the fee taken.
+fn skim_proxy_fee<'info>(
+    program_id: &Pubkey,
+    swap_accounts: &[AccountInfo<'info>],
+    config: &AccountInfo,
+    fee_vault: &AccountInfo<'info>,
+    direction: TradeDirection,
+    amount_in: u64,
+) -> Result<u64, ProgramError> {
+
+    let payer = &swap_accounts[0];
+    let (input_token_account, _) = swap_token_accounts(swap_accounts, direction);
+    let (input_mint, input_token_program) = match direction {
+        TradeDirection::BUY => (&swap_accounts[7], &swap_accounts[9]),
+        TradeDirection::SELL => (&swap_accounts[8], &swap_accounts[10]),
+    };
+
+    // --- Validation Checks ---
+    let proxy_config = ProxyConfig::load(program_id, config)?;
+
+    if fee_vault.key != &Pda::fee_vault(config.key, input_mint.key, input_token_program.key) {
+        msg!("Fee vault {} is not the config token account of {}", fee_vault.key, input_mint.key);
+        return Err(MeteoraProxyError::InvalidFeeVault.into());
+    }
+
+    let fee = proxy_config.fee_on(amount_in);
+    if fee == 0 {
+        return Ok(0);
+    }
+
+    msg!("proxy fee : {}", fee);
+
+    TokenAccount::transfer(
+        input_token_program,
+        input_token_account,
+        input_mint,
+        fee_vault,
+        payer,
+        fee,
+        &[],
+    )?;
+
+    Ok(fee)
+}
+
+
+
+/// `invoke_swap` for a swap the payer signed, after skimming the proxy fee.
+///
+/// The fee on `params.amount_in` goes to `fee_vault` and the rest is swapped; the
+/// returned `amount_in` includes the fee.
+fn invoke_swap_with_proxy_fee<'info>(
+    program_id: &Pubkey,
+    swap_accounts: &[AccountInfo<'info>],
+    config: &AccountInfo,
+    fee_vault: &AccountInfo<'info>,
+    mut params: MeteoraDammV2PoolSwapParams,
+    direction: TradeDirection,
+) -> Result<SwapResult, ProgramError> {
+
+    let proxy_fee = skim_proxy_fee(program_id, swap_accounts, config, fee_vault, direction, params.amount_in)?;
+
+    params.amount_in -= proxy_fee;
+
+    let mut instruction_data_cpi = SWAP_DISCRIMINATOR.to_vec();
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    let mut result = invoke_swap(
+        program_id,
+        swap_accounts,
+        direction,
+        instruction_data_cpi,
+        params.minimum_amount_out,
+        Authority::Signer,
+    )?;
+
+    result.amount_in += proxy_fee;
+
+    Ok(result)
+}
+
+
+
+/// Number of accounts `invoke_swap` takes.
+const SWAP_ACCOUNTS_LEN: usize = 15;
+
+/// The instruction discriminator for `swap`.
+const SWAP_DISCRIMINATOR: [u8; 8] = [248,198,158,145,225,117,135,200];
+
+
+
+/// Accounts of one `invoke_swap` group, for handlers that swap with accounts they parsed
+/// for another CPI.
+struct SwapAccounts<'a, 'info> {
+    payer: &'a AccountInfo<'info>,
+    pool_authority: &'a AccountInfo<'info>,
+    pool: &'a AccountInfo<'info>,
+    token_a_account: &'a AccountInfo<'info>,
+    token_b_account: &'a AccountInfo<'info>,
+    token_a_vault: &'a AccountInfo<'info>,
+    token_b_vault: &'a AccountInfo<'info>,
+    token_a_mint: &'a AccountInfo<'info>,
+    token_b_mint: &'a AccountInfo<'info>,
+    token_a_program: &'a AccountInfo<'info>,
+    token_b_program: &'a AccountInfo<'info>,
+    referral_token_account: &'a AccountInfo<'info>,
+    event_authority: &'a AccountInfo<'info>,
+    meteora_program: &'a AccountInfo<'info>,
+    allowed_pool: &'a AccountInfo<'info>,
+}
+
+impl<'info> SwapAccounts<'_, 'info> {
+
+    /// The group in the order `invoke_swap` reads it.
+    fn group(&self) -> [AccountInfo<'info>; SWAP_ACCOUNTS_LEN] {
+        [
+            self.payer.clone(),
+            self.pool_authority.clone(),
+            self.pool.clone(),
+            self.token_a_account.clone(),
+            self.token_b_account.clone(),
+            self.token_a_vault.clone(),
+            self.token_b_vault.clone(),
+            self.token_a_mint.clone(),
+            self.token_b_mint.clone(),
+            self.token_a_program.clone(),
+            self.token_b_program.clone(),
+            self.referral_token_account.clone(),
+            self.event_authority.clone(),
+            self.meteora_program.clone(),
+            self.allowed_pool.clone(),
+        ]
+    }
+}
+
+
+
+/// Returns the input and output token accounts of a `cpi_swap` account group.
+///
+/// Mirrors the BUY / SELL handling of `invoke_swap`: the group always lists the token A
+/// account before the token B account.
+fn swap_token_accounts<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    direction: TradeDirection,
+) -> (&'a AccountInfo<'info>, &'a AccountInfo<'info>) {
+    match direction {
+        TradeDirection::BUY => (&accounts[3], &accounts[4]),
+        TradeDirection::SELL => (&accounts[4], &accounts[3]),
+    }
+}
+
+
+
+/// Shared account handling for `swap` and `swap2`.
+///
+/// Both Meteora instructions take the same accounts, so only the instruction data differs.
+/// The output account balance is measured around the CPI and `minimum_amount_out` is
+/// enforced on what actually arrived, so Token-2022 transfer fees are accounted for.
+/// Returns the realized amounts for the caller to publish.
+fn invoke_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    direction: TradeDirection,
+    instruction_data_cpi: Vec<u8>,
+    minimum_amount_out: u64,
+    authority: Authority,
+) -> Result<SwapResult, ProgramError> {
+    
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+
+    let (
+        input_token_account,
+        output_token_account,
+
+    ) = match direction {
+
+        TradeDirection::BUY => {
+
+            let input_token_account         = next_account_info(accounts_iter)?;
+            let output_token_account        = next_account_info(accounts_iter)?;
+            
+            (input_token_account,output_token_account)
+        },
+
+        TradeDirection::SELL => {
+
+            let input_token_account         = next_account_info(accounts_iter)?;
+            let output_token_account        = next_account_info(accounts_iter)?;
+            
+            (output_token_account,input_token_account)
+        }
+    };
+
+    
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;    
+    let referral_token_account      = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+    
+    
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    let signer = authority.check(program_id, payer)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_pool_mints(pool, token_a_mint, token_b_mint, meteora_program.key)?;
+    Check::check_allowed_pool(program_id, allowed_pool, pool)?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+    authority.check_destination(program_id, output_token_account)?;
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `swap` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*input_token_account.key, false),
+        AccountMeta::new(*output_token_account.key, false),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new_readonly(*token_a_mint.key, false),
+        AccountMeta::new_readonly(*token_b_mint.key, false),
+        AccountMeta::new_readonly(*payer.key, true),
+        AccountMeta::new_readonly(*token_a_program.key, false),
+        AccountMeta::new_readonly(*token_b_program.key, false),
+        // referral account 暂时跳过
+        AccountMeta::new(*referral_token_account.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool_authority.clone(),
+        pool.clone(),
+        input_token_account.clone(),
+        output_token_account.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        token_a_mint.clone(),
+        token_b_mint.clone(),
+        payer.clone(),
+        token_a_program.clone(),
+        token_b_program.clone(),
+        referral_token_account.clone(), 
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    let input_before = TokenAccount::amount(input_token_account)?;
+    let output_before = TokenAccount::amount(output_token_account)?;
+    let fee_before = read_pool_total_fee(pool)?;
+
+    msg!("Invoking Meteora DAMM program to swap...");
+
+    signer.invoke(
+        &cpi_instruction, 
+        account_infos
+    )?;
+
+    // --- Enforce Minimum Out ---
+    let amount_in = TokenAccount::spent_since(input_token_account, input_before)?;
+    let amount_out = TokenAccount::received_since(output_token_account, output_before)?;
+
+    msg!("realized amount in : {}", amount_in);
+    msg!("realized amount out : {}", amount_out);
+
+    if amount_out < minimum_amount_out {
+        msg!("Received {} is below the minimum amount out {}", amount_out, minimum_amount_out);
+        return Err(MeteoraProxyError::SlippageExceeded.into());
+    }
+    
+    let result = SwapResult {
+        amount_in,
+        amount_out,
+        fee: read_pool_total_fee(pool)?.saturating_sub(fee_before).try_into().unwrap_or(u64::MAX),
+        sqrt_price: read_pool(pool)?.sqrt_price,
+    };
+
+    msg!("fee : {}", result.fee);
+    msg!("Swap executed successfully via CPI");
+
+    Ok(result)
+}
+
+
+
+/// Calls the Meteora DAMM `add_liquidity` instruction.
+///
+/// Deposits token A and token B into an existing position of the pool. The
+/// thresholds in `params` are the maximum amounts the owner is willing to pay.
+fn cpi_add_liquidity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: AddLiquidityParameters,
+    authority: Authority,
+) -> ProgramResult {
+
+    msg!("liquidity delta : {}", params.liquidity_delta);
+    msg!("token a max : {}", params.token_a_amount_threshold);
+    msg!("token b max : {}", params.token_b_amount_threshold);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let token_a_account             = next_account_info(accounts_iter)?;
+    let token_b_account             = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    let signer = authority.check(program_id, owner)?;
+    Check::check_any_token_program(token_a_program)?;
+    Check::check_any_token_program(token_b_program)?;
+    Check::check_pool_mints(pool, token_a_mint, token_b_mint, meteora_program.key)?;
+    Check::check_allowed_pool(program_id, allowed_pool, pool)?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The instruction discriminator for `add_liquidity`
+    let mut instruction_data_cpi = vec![181,157,89,67,143,182,52,72];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `add_liquidity` instruction.
+    let account_metas = vec![
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new(*token_a_account.key, false),
+        AccountMeta::new(*token_b_account.key, false),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new_readonly(*token_a_mint.key, false),
+        AccountMeta::new_readonly(*token_b_mint.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*token_a_program.key, false),
+        AccountMeta::new_readonly(*token_b_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool.clone(),
+        position.clone(),
+        token_a_account.clone(),
+        token_b_account.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        token_a_mint.clone(),
+        token_b_mint.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        token_a_program.clone(),
+        token_b_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    let token_a_before = TokenAccount::amount(token_a_account)?;
+    let token_b_before = TokenAccount::amount(token_b_account)?;
+    let liquidity_before = read_pool(pool)?.liquidity;
+
+    msg!("Invoking Meteora DAMM program to add liquidity...");
+
+    signer.invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    let pool_after = read_pool(pool)?;
+    let result = LiquidityResult {
+        token_a_amount: TokenAccount::spent_since(token_a_account, token_a_before)?,
+        token_b_amount: TokenAccount::spent_since(token_b_account, token_b_before)?,
+        liquidity_delta: pool_after.liquidity.saturating_sub(liquidity_before),
+        sqrt_price: pool_after.sqrt_price,
+    };
+
+    msg!("token a deposited : {}", result.token_a_amount);
+    msg!("token b deposited : {}", result.token_b_amount);
+
+    result.publish();
+
+    msg!("Liquidity added successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Deposits a single token: calls Meteora DAMM `swap`, then `add_liquidity` into a position.
+///
+/// The swap amount is chosen with `zap_in_swap_amount` so the remaining input and the
+/// swap output, net of the proxy fee, match the pool ratio after the swap. The swap output is guarded by
+/// `max_slippage_bps` against the on-chain quote and pays the proxy fee like `cpi_swap`.
+/// Whatever the deposit does not use stays in the owner's token accounts. Takes the
+/// `cpi_add_liquidity` accounts followed by the pool authority, the referral token account
+/// and the fee vault of the input mint; publishes the `LiquidityResult`.
+fn cpi_zap_in(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    amount_in: u64,
+    input_is_a: bool,
+    max_slippage_bps: u64,
+) -> ProgramResult {
+
+    msg!("amount in : {}", amount_in);
+    msg!("input is a : {}", input_is_a);
+
+    if max_slippage_bps > 10_000 {
+        msg!("Slippage {} bps is above 10000", max_slippage_bps);
+        return Err(MeteoraProxyError::InvalidSlippage.into());
+    }
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let _position                   = next_account_info(accounts_iter)?;
+    let token_a_account             = next_account_info(accounts_iter)?;
+    let token_b_account             = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let _position_nft_account       = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let referral_token_account      = next_account_info(accounts_iter)?;
+    let fee_vault                   = next_account_info(accounts_iter)?;
+
+    // --- Validation Checks ---
+    Check::check_pool_mints(pool, token_a_mint, token_b_mint, &program_id::METEORA_PROGRAM_ID)?;
+
+    let pool_state = read_pool(pool)?;
+    let pool_fee = read_pool_fee(pool)?;
+    let (sqrt_min_price, sqrt_max_price) = read_pool_price_range(pool)?;
+
+    let proxy_config = ProxyConfig::load(program_id, config)?;
+
+    let swap_amount = zap_in_swap_amount(
+        &pool_state,
+        &pool_fee,
+        sqrt_min_price,
+        sqrt_max_price,
+        input_is_a,
+        amount_in,
+        proxy_config.fee_bps,
+    ).ok_or(MeteoraProxyError::MathOverflow)?;
+
+    msg!("swap amount : {}", swap_amount);
+
+    let (swapped_in, swapped_out) = if swap_amount == 0 {
+        (0, 0)
+    } else {
+
+        let proxy_fee = proxy_config.fee_on(swap_amount);
+        let expected_out = quote(&pool_state, &pool_fee, input_is_a, swap_amount - proxy_fee)
+            .ok_or(MeteoraProxyError::MathOverflow)?
+            .amount_out;
+
+        let params = MeteoraDammV2PoolSwapParams {
+            amount_in: swap_amount,
+            minimum_amount_out: expected_out.saturating_mul(10_000 - max_slippage_bps) / 10_000,
+        };
+
+        let swap_accounts = SwapAccounts {
+            payer: owner,
+            pool_authority,
+            pool,
+            token_a_account,
+            token_b_account,
+            token_a_vault,
+            token_b_vault,
+            token_a_mint,
+            token_b_mint,
+            token_a_program,
+            token_b_program,
+            referral_token_account,
+            event_authority,
+            meteora_program,
+            allowed_pool,
+        }.group();
+
+        let direction = if input_is_a { TradeDirection::BUY } else { TradeDirection::SELL };
+
+        let result = invoke_swap_with_proxy_fee(
+            program_id,
+            &swap_accounts,
+            config,
+            fee_vault,
+            params,
+            direction,
+        )?;
+
+        (result.amount_in, result.amount_out)
+    };
+
+    // --- Size The Deposit ---
+    // Leave one token of headroom on each side for the cp-amm round up.
+    let remaining = amount_in.saturating_sub(swapped_in);
+    let (token_a_amount, token_b_amount) = if input_is_a {
+        (remaining, swapped_out)
+    } else {
+        (swapped_out, remaining)
+    };
+
+    let sqrt_price = read_pool(pool)?.sqrt_price;
+    let liquidity_delta = liquidity_from_amount_a(token_a_amount.saturating_sub(1), sqrt_price, sqrt_max_price)
+        .min(liquidity_from_amount_b(token_b_amount.saturating_sub(1), sqrt_price, sqrt_min_price));
+
+    let params = AddLiquidityParameters {
+        liquidity_delta,
+        token_a_amount_threshold: token_a_amount,
+        token_b_amount_threshold: token_b_amount,
+    };
+
+    cpi_add_liquidity(
+        program_id,
+        accounts,
+        params,
+        Authority::Signer,
+    )
+}
+
+
+
+/// Withdraws into a single token: calls Meteora DAMM `remove_liquidity`, then `swap`.
+///
+/// The withdrawn amount of the unwanted token is swapped back through the same pool.
+/// Neither CPI has a minimum of its own; `minimum_out` is enforced once on the combined
+/// output token amount. The swap pays the proxy fee like `cpi_swap`. Takes the
+/// `cpi_remove_liquidity` accounts followed by the referral token account and the fee
+/// vault of the swapped mint; publishes a `ZapOutResult`.
+fn cpi_zap_out(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    liquidity_delta: u128,
+    output_is_a: bool,
+    minimum_out: u64,
+) -> ProgramResult {
+
+    msg!("liquidity delta : {}", liquidity_delta);
+    msg!("output is a : {}", output_is_a);
+    msg!("minimum out : {}", minimum_out);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let _position                   = next_account_info(accounts_iter)?;
+    let token_a_account             = next_account_info(accounts_iter)?;
+    let token_b_account             = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let _position_nft_account       = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+    let referral_token_account      = next_account_info(accounts_iter)?;
+    let fee_vault                   = next_account_info(accounts_iter)?;
+
+    let params = RemoveLiquidityParameters {
+        liquidity_delta,
+        token_a_amount_threshold: 0,
+        token_b_amount_threshold: 0,
+    };
+
+    // The instruction discriminator for `remove_liquidity`
+    let mut instruction_data_cpi = vec![80,85,209,72,24,206,177,108];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    let liquidity = invoke_remove_liquidity(
+        program_id,
+        accounts,
+        instruction_data_cpi,
+        0,
+        0,
+        Authority::Signer,
+    )?;
+
+    // Swap the side the owner does not want: token B for an A output, token A otherwise.
+    let (direction, swap_amount, withdrawn_out) = if output_is_a {
+        (TradeDirection::SELL, liquidity.token_b_amount, liquidity.token_a_amount)
+    } else {
+        (TradeDirection::BUY, liquidity.token_a_amount, liquidity.token_b_amount)
+    };
+
+    let swap = if swap_amount == 0 {
+        SwapResult {
+            amount_in: 0,
+            amount_out: 0,
+            fee: 0,
+            sqrt_price: liquidity.sqrt_price,
+        }
+    } else {
+
+        let params = MeteoraDammV2PoolSwapParams {
+            amount_in: swap_amount,
+            minimum_amount_out: 0,
+        };
+
+        let swap_accounts = SwapAccounts {
+            payer: owner,
+            pool_authority,
+            pool,
+            token_a_account,
+            token_b_account,
+            token_a_vault,
+            token_b_vault,
+            token_a_mint,
+            token_b_mint,
+            token_a_program,
+            token_b_program,
+            referral_token_account,
+            event_authority,
+            meteora_program,
+            allowed_pool,
+        }.group();
+
+        invoke_swap_with_proxy_fee(
+            program_id,
+            &swap_accounts,
+            config,
+            fee_vault,
+            params,
+            direction,
+        )?
+    };
+
+    // --- Enforce Minimum Out ---
+    let result = ZapOutResult {
+        liquidity,
+        swap,
+        amount_out: withdrawn_out.saturating_add(swap.amount_out),
+    };
+
+    msg!("total amount out : {}", result.amount_out);
+
+    if result.amount_out < minimum_out {
+        msg!("Received {} is below the minimum amount out {}", result.amount_out, minimum_out);
+        return Err(MeteoraProxyError::SlippageExceeded.into());
+    }
+
+    result.publish();
+
+    msg!("Zap out executed successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `remove_liquidity` instruction.
+///
+/// Withdraws `liquidity_delta` from a position and fails if the owner receives less
+/// than the token A / token B minimums.
+fn cpi_remove_liquidity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: RemoveLiquidityParameters,
+    authority: Authority,
+) -> ProgramResult {
+
+    msg!("liquidity delta : {}", params.liquidity_delta);
+    msg!("token a min : {}", params.token_a_amount_threshold);
+    msg!("token b min : {}", params.token_b_amount_threshold);
+
+    // The instruction discriminator for `remove_liquidity`
+    let mut instruction_data_cpi = vec![80,85,209,72,24,206,177,108];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    let result = invoke_remove_liquidity(
+        program_id,
+        accounts,
+        instruction_data_cpi,
+        params.token_a_amount_threshold,
+        params.token_b_amount_threshold,
+        authority,
+    )?;
+
+    result.publish();
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `remove_all_liquidity` instruction.
+///
+/// Withdraws all unlocked liquidity from a position and fails if the owner receives
+/// less than the token A / token B minimums.
+fn cpi_remove_all_liquidity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: RemoveAllLiquidityParameters,
+    authority: Authority,
+) -> ProgramResult {
+
+    msg!("token a min : {}", params.token_a_amount_threshold);
+    msg!("token b min : {}", params.token_b_amount_threshold);
+
+    // The instruction discriminator for `remove_all_liquidity`
+    let mut instruction_data_cpi = vec![10,51,61,35,112,105,24,85];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    let result = invoke_remove_liquidity(
+        program_id,
+        accounts,
+        instruction_data_cpi,
+        params.token_a_amount_threshold,
+        params.token_b_amount_threshold,
+        authority,
+    )?;
+
+    result.publish();
+
+    Ok(())
+}
+
+
+
+/// Shared account handling for `remove_liquidity` and `remove_all_liquidity`.
+///
+/// Both Meteora instructions take the same accounts, so only the instruction data
+/// differs. The owner's token balances are measured around the CPI to enforce the
+/// minimum amounts on what actually arrived.
+/// Returns the realized amounts for the caller to publish.
+fn invoke_remove_liquidity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data_cpi: Vec<u8>,
+    token_a_minimum: u64,
+    token_b_minimum: u64,
+    authority: Authority,
+) -> Result<LiquidityResult, ProgramError> {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let token_a_account             = next_account_info(accounts_iter)?;
+    let token_b_account             = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    let signer = authority.check(program_id, owner)?;
+    Check::check_any_token_program(token_a_program)?;
+    Check::check_any_token_program(token_b_program)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_pool_mints(pool, token_a_mint, token_b_mint, meteora_program.key)?;
+    Check::check_allowed_pool(program_id, allowed_pool, pool)?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+    authority.check_destination(program_id, token_a_account)?;
+    authority.check_destination(program_id, token_b_account)?;
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `remove_liquidity` / `remove_all_liquidity` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new(*token_a_account.key, false),
+        AccountMeta::new(*token_b_account.key, false),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new_readonly(*token_a_mint.key, false),
+        AccountMeta::new_readonly(*token_b_mint.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*token_a_program.key, false),
+        AccountMeta::new_readonly(*token_b_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool_authority.clone(),
+        pool.clone(),
+        position.clone(),
+        token_a_account.clone(),
+        token_b_account.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        token_a_mint.clone(),
+        token_b_mint.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        token_a_program.clone(),
+        token_b_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    let token_a_before = TokenAccount::amount(token_a_account)?;
+    let token_b_before = TokenAccount::amount(token_b_account)?;
+    let liquidity_before = read_pool(pool)?.liquidity;
+
+    msg!("Invoking Meteora DAMM program to remove liquidity...");
+
+    signer.invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    // --- Enforce Minimum Out ---
+    let token_a_received = TokenAccount::received_since(token_a_account, token_a_before)?;
+    let token_b_received = TokenAccount::received_since(token_b_account, token_b_before)?;
+
+    msg!("token a received : {}", token_a_received);
+    msg!("token b received : {}", token_b_received);
+
+    if token_a_received < token_a_minimum || token_b_received < token_b_minimum {
+        msg!("Received amounts are below the requested minimum");
+        return Err(MeteoraProxyError::SlippageExceeded.into());
+    }
+
+    let pool_after = read_pool(pool)?;
+
+    msg!("Liquidity removed successfully via CPI");
+
+    Ok(LiquidityResult {
+        token_a_amount: token_a_received,
+        token_b_amount: token_b_received,
+        liquidity_delta: liquidity_before.saturating_sub(pool_after.liquidity),
+        sqrt_price: pool_after.sqrt_price,
+    })
+}
+
+
+
+/// Calls the Meteora DAMM `create_position` instruction.
+///
+/// Opens an additional position on an existing pool. The `position` and
+/// `position_nft_account` PDAs are derived from the freshly generated NFT mint.
+fn cpi_create_position(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let owner                       = next_account_info(accounts_iter)?;
+    let position_nft_mint           = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let token_2022_program          = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(payer)?;
+    Check::check_is_signer(position_nft_mint)?;
+    Check::check_token_2022_program(token_2022_program)?;
+    Check::check_system_program(system_program)?;
+    Check::check_pda(position, &Pda::position(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pda(position_nft_account, &Pda::position_nft_account(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The instruction discriminator for `create_position`
+    let instruction_data_cpi = vec![48,215,197,153,96,203,180,133];
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `create_position` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*owner.key, false),
+        AccountMeta::new(*position_nft_mint.key, true),
+        AccountMeta::new(*position_nft_account.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*payer.key, true),
+        AccountMeta::new_readonly(*token_2022_program.key, false),
+        AccountMeta::new_readonly(*system_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        owner.clone(),
+        position_nft_mint.clone(),
+        position_nft_account.clone(),
+        pool.clone(),
+        position.clone(),
+        pool_authority.clone(),
+        payer.clone(),
+        token_2022_program.clone(),
+        system_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to create position...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Position created successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `close_position` instruction.
+///
+/// Closes an empty position, burning its NFT and returning the rent to `rent_receiver`.
+fn cpi_close_position(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let position_nft_mint           = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let rent_receiver               = next_account_info(accounts_iter)?;
+    let token_2022_program          = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(owner)?;
+    Check::check_token_2022_program(token_2022_program)?;
+    Check::check_pda(position, &Pda::position(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pda(position_nft_account, &Pda::position_nft_account(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The instruction discriminator for `close_position`
+    let instruction_data_cpi = vec![123,134,81,0,49,68,98,98];
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `close_position` instruction.
+    let account_metas = vec![
+        AccountMeta::new(*position_nft_mint.key, false),
+        AccountMeta::new(*position_nft_account.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*rent_receiver.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*token_2022_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        position_nft_mint.clone(),
+        position_nft_account.clone(),
+        pool.clone(),
+        position.clone(),
+        pool_authority.clone(),
+        rent_receiver.clone(),
+        owner.clone(),
+        token_2022_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to close position...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Position closed successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `split_position` instruction.
+///
+/// Moves the requested percentages of liquidity, fees and rewards from the first
+/// position into the second one. Both positions must belong to the same pool.
+fn cpi_split_position(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: SplitPositionParameters,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let first_owner                 = next_account_info(accounts_iter)?;
+    let second_owner                = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let first_position              = next_account_info(accounts_iter)?;
+    let first_position_nft_account  = next_account_info(accounts_iter)?;
+    let second_position             = next_account_info(accounts_iter)?;
+    let second_position_nft_account = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(first_owner)?;
+    Check::check_is_signer(second_owner)?;
+
+    Check::check_position_accounts(first_position, first_position_nft_account, meteora_program.key)?;
+    Check::check_position_accounts(second_position, second_position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The instruction discriminator for `split_position`
+    let mut instruction_data_cpi = vec![172,241,221,138,161,29,253,42];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `split_position` instruction.
+    let account_metas = vec![
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*first_position.key, false),
+        AccountMeta::new_readonly(*first_position_nft_account.key, false),
+        AccountMeta::new(*second_position.key, false),
+        AccountMeta::new_readonly(*second_position_nft_account.key, false),
+        AccountMeta::new_readonly(*first_owner.key, true),
+        AccountMeta::new_readonly(*second_owner.key, true),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool.clone(),
+        first_position.clone(),
+        first_position_nft_account.clone(),
+        second_position.clone(),
+        second_position_nft_account.clone(),
+        first_owner.clone(),
+        second_owner.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to split position...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Position split successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `claim_position_fee` instruction.
+///
+/// Transfers the pending token A / token B trading fees of a position to the owner.
+fn cpi_claim_position_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Authority,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let token_a_account             = next_account_info(accounts_iter)?;
+    let token_b_account             = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    let signer = authority.check(program_id, owner)?;
+    Check::check_any_token_program(token_a_program)?;
+    Check::check_any_token_program(token_b_program)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_pool_mints(pool, token_a_mint, token_b_mint, meteora_program.key)?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+    authority.check_destination(program_id, token_a_account)?;
+    authority.check_destination(program_id, token_b_account)?;
+
+    // The instruction discriminator for `claim_position_fee`
+    let instruction_data_cpi = vec![180,38,154,17,133,33,162,211];
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `claim_position_fee` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new_readonly(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new(*token_a_account.key, false),
+        AccountMeta::new(*token_b_account.key, false),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new_readonly(*token_a_mint.key, false),
+        AccountMeta::new_readonly(*token_b_mint.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*token_a_program.key, false),
+        AccountMeta::new_readonly(*token_b_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool_authority.clone(),
+        pool.clone(),
+        position.clone(),
+        token_a_account.clone(),
+        token_b_account.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        token_a_mint.clone(),
+        token_b_mint.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        token_a_program.clone(),
+        token_b_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    let token_a_before = TokenAccount::amount(token_a_account)?;
+    let token_b_before = TokenAccount::amount(token_b_account)?;
+
+    msg!("Invoking Meteora DAMM program to claim position fee...");
+
+    signer.invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("fee a claimed : {}", TokenAccount::received_since(token_a_account, token_a_before)?);
+    msg!("fee b claimed : {}", TokenAccount::received_since(token_b_account, token_b_before)?);
+
+    msg!("Position fee claimed successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `claim_reward` instruction.
+///
+/// Claims the farming reward of slot `reward_index` for a position. The reward mint
+/// may belong to either SPL Token or SPL Token 2022.
+fn cpi_claim_reward(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    reward_index: u8,
+) -> ProgramResult {
+
+    msg!("reward index : {}", reward_index);
+
+    if reward_index as usize >= NUM_REWARDS {
+        msg!("Reward index must be lower than {}", NUM_REWARDS);
+        return Err(MeteoraProxyError::InvalidRewardIndex.into());
+    }
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let reward_vault                = next_account_info(accounts_iter)?;
+    let reward_mint                 = next_account_info(accounts_iter)?;
+    let user_token_account          = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let token_program               = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(owner)?;
+    Check::check_any_token_program(token_program)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_pda(reward_vault, &Pda::reward_vault(pool.key, reward_index, meteora_program.key))?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The reward mint decides which token program has to move the reward.
+    if reward_mint.owner != token_program.key {
+        msg!("Reward mint is not owned by token program {}", token_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // The instruction discriminator for `claim_reward`
+    // args: reward_index, skip_reward (always claim)
+    let mut instruction_data_cpi = vec![149,95,181,242,94,90,158,162];
+    instruction_data_cpi.extend_from_slice(&[reward_index, 0]);
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `claim_reward` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new(*reward_vault.key, false),
+        AccountMeta::new_readonly(*reward_mint.key, false),
+        AccountMeta::new(*user_token_account.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*token_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool_authority.clone(),
+        pool.clone(),
+        position.clone(),
+        reward_vault.clone(),
+        reward_mint.clone(),
+        user_token_account.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        token_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    let reward_before = TokenAccount::amount(user_token_account)?;
+
+    msg!("Invoking Meteora DAMM program to claim reward...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("reward claimed : {}", TokenAccount::received_since(user_token_account, reward_before)?);
+
+    msg!("Reward claimed successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `lock_position` instruction.
+///
+/// Moves liquidity of a position into a fresh `vesting` account that releases it after
+/// the cliff and then per period. The parameters are validated against the vesting
+/// limits of the pool activation type before the CPI.
+fn cpi_lock_position(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: VestingParameters,
+) -> ProgramResult {
+
+    msg!("cliff point : {:?}", params.cliff_point);
+    msg!("period frequency : {}", params.period_frequency);
+    msg!("number of period : {}", params.number_of_period);
+    msg!("liquidity per period : {}", params.liquidity_per_period);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let owner                       = next_account_info(accounts_iter)?;
+    let vesting                     = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(payer)?;
+    Check::check_is_signer(owner)?;
+    Check::check_is_signer(vesting)?;
+    Check::check_system_program(system_program)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // Vesting is measured in slots or seconds depending on how the pool activates.
+    let activation_type = *pool
+        .try_borrow_data()?
+        .get(ACTIVATION_TYPE_BEGIN)
+        .ok_or(MeteoraProxyError::InvalidPoolAccount)?;
+
+    let clock = Clock::get()?;
+    let (current_point, max_vesting_duration) = match activation_type {
+        0 => (clock.slot, MAX_VESTING_SLOT_DURATION),
+        _ => (clock.unix_timestamp as u64, MAX_VESTING_TIME_DURATION),
+    };
+
+    if let Err(reason) = params.validate(current_point, max_vesting_duration) {
+        msg!("Invalid vesting parameters: {}", reason);
+        return Err(MeteoraProxyError::InvalidVestingParameters.into());
+    }
+
+    // The instruction discriminator for `lock_position`
+    let mut instruction_data_cpi = vec![227,62,2,252,247,10,171,185];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `lock_position` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new(*vesting.key, true),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new(*payer.key, true),
+        AccountMeta::new_readonly(*system_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool.clone(),
+        position.clone(),
+        vesting.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        payer.clone(),
+        system_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to lock position...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Position locked successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `permanent_lock_position` instruction.
+///
+/// Locks `permanent_lock_liquidity` of a position forever. Fees can still be claimed.
+fn cpi_permanent_lock_position(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    permanent_lock_liquidity: u128,
+) -> ProgramResult {
+
+    msg!("permanent lock liquidity : {}", permanent_lock_liquidity);
+
+    if permanent_lock_liquidity == 0 {
+        msg!("Permanent lock liquidity must be greater than zero");
+        return Err(MeteoraProxyError::InvalidLockLiquidity.into());
+    }
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(owner)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    // The instruction discriminator for `permanent_lock_position`
+    let mut instruction_data_cpi = vec![165,176,125,6,231,171,186,213];
+    instruction_data_cpi.extend_from_slice(&permanent_lock_liquidity.to_le_bytes());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `permanent_lock_position` instruction.
+    let account_metas = vec![
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, true),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false),
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        pool.clone(),
+        position.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to permanently lock position...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Position permanently locked successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `refresh_vesting` instruction.
+///
+/// Releases the vested liquidity of a position. Every account after the Meteora program
+/// is forwarded as a writable `vesting` account.
+fn cpi_refresh_vesting(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let owner                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let vestings: Vec<&AccountInfo> = accounts_iter.collect();
+
+    msg!("vesting accounts : {}", vestings.len());
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_position_accounts(position, position_nft_account, meteora_program.key)?;
+
+    // The instruction discriminator for `refresh_vesting`
+    let instruction_data_cpi = vec![9,94,216,14,116,204,247,0];
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `refresh_vesting` instruction, followed by the vestings.
+    let mut account_metas = vec![
+        AccountMeta::new_readonly(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new_readonly(*position_nft_account.key, false),
+        AccountMeta::new_readonly(*owner.key, false),
+    ];
+    account_metas.extend(vestings.iter().map(|vesting| AccountMeta::new(*vesting.key, false)));
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let mut account_infos = vec![
+        pool.clone(),
+        position.clone(),
+        position_nft_account.clone(),
+        owner.clone(),
+    ];
+    account_infos.extend(vestings.into_iter().cloned());
+    account_infos.push(meteora_program.clone()); // The program being called must be in account_infos
+
+    msg!("Invoking Meteora DAMM program to refresh vesting...");
+
+    invoke(
+        &cpi_instruction,
+        &account_infos
+    )?;
+
+    msg!("Vesting refreshed successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `initialize_customizable_pool` instruction.
+///
+/// Unlike `cpi_initialize_pool` no `config` account is used: the fee schedule and the
+/// sqrt price range come from `params`, and the pool is derived with the `cpool` seed.
+#[allow(clippy::too_many_arguments)]
+fn cpi_initialize_customizable_pool(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: InitializeCustomizablePoolParameters,
+) -> ProgramResult {
+
+    msg!("sqrt min price : {}", params.sqrt_min_price);
+    msg!("sqrt max price : {}", params.sqrt_max_price);
+    msg!("sqrt price : {}", params.sqrt_price);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let creator                     = next_account_info(accounts_iter)?;
+    let position_nft_mint           = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let payer_token_a               = next_account_info(accounts_iter)?;
+    let payer_token_b               = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let token_2022_program          = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(payer)?;
+    Check::check_is_signer(position_nft_mint)?;
+    Check::check_any_token_program(token_a_program)?;
+    Check::check_any_token_program(token_b_program)?;
+    Check::check_token_2022_program(token_2022_program)?;
+    Check::check_system_program(system_program)?;
+    Check::check_pda(pool, &Pda::customizable_pool(token_a_mint.key, token_b_mint.key, meteora_program.key))?;
+    Check::check_pda(position, &Pda::position(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pda(position_nft_account, &Pda::position_nft_account(position_nft_mint.key, meteora_program.key))?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    if let Err(reason) = params.validate() {
+        msg!("Invalid customizable pool parameters: {}", reason);
+        return Err(MeteoraProxyError::InvalidPoolParameters.into());
+    }
+
+    // The instruction discriminator for `initialize_customizable_pool`
+    let mut instruction_data_cpi = vec![20,161,241,24,189,221,180,2];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `initialize_customizable_pool` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*creator.key, false),
+        AccountMeta::new(*position_nft_mint.key, true),
+        AccountMeta::new(*position_nft_account.key, false),
+        AccountMeta::new(*payer.key, true),
+        AccountMeta::new_readonly(*pool_authority.key, false),
+        AccountMeta::new(*pool.key, false),
+        AccountMeta::new(*position.key, false),
+        AccountMeta::new_readonly(*token_a_mint.key, false),
+        AccountMeta::new_readonly(*token_b_mint.key, false),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new(*payer_token_a.key, false),
+        AccountMeta::new(*payer_token_b.key, false),
+        AccountMeta::new_readonly(*token_a_program.key, false),
+        AccountMeta::new_readonly(*token_b_program.key, false),
+        AccountMeta::new_readonly(*token_2022_program.key, false),
+        AccountMeta::new_readonly(*system_program.key, false),
+        AccountMeta::new_readonly(*event_authority.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false)
+    ];
+
+    // --- Create and Invoke CPI ---
+    let cpi_instruction = Instruction {
+        program_id: *meteora_program.key,
+        accounts: account_metas,
+        data: instruction_data_cpi,
+    };
+
+    let account_infos = &[
+        creator.clone(),
+        position_nft_mint.clone(),
+        position_nft_account.clone(),
+        payer.clone(),
+        pool_authority.clone(),
+        pool.clone(),
+        position.clone(),
+        token_a_mint.clone(),
+        token_b_mint.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        payer_token_a.clone(),
+        payer_token_b.clone(),
+        token_a_program.clone(),
+        token_b_program.clone(),
+        token_2022_program.clone(),
+        system_program.clone(),
+        event_authority.clone(),
+        meteora_program.clone(), // The program being called must be in account_infos
+    ];
+
+    msg!("Invoking Meteora DAMM program to initialize customizable pool...");
+
+    invoke(
+        &cpi_instruction,
+        account_infos
+    )?;
+
+    msg!("Customizable pool initialized successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Calls the Meteora DAMM `initialize_pool_with_dynamic_config` instruction.
+///
+/// Dynamic configs only fix the pool creator authority; the fee schedule and price range
+/// come from `params`. The `config` must be a Meteora config account whose
+/// `pool_creator_authority` signed this transaction.
+#[allow(clippy::too_many_arguments)]
+fn cpi_initialize_pool_with_dynamic_config(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: InitializeCustomizablePoolParameters,
+) -> ProgramResult {
+
+    msg!("sqrt min price : {}", params.sqrt_min_price);
+    msg!("sqrt max price : {}", params.sqrt_max_price);
+    msg!("sqrt price : {}", params.sqrt_price);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let payer                       = next_account_info(accounts_iter)?;
+    let creator                     = next_account_info(accounts_iter)?;
+    let position_nft_mint           = next_account_info(accounts_iter)?;
+    let position_nft_account        = next_account_info(accounts_iter)?;
+    let pool_creator_authority      = next_account_info(accounts_iter)?;
+    let config                      = next_account_info(accounts_iter)?;
+    let pool_authority              = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let position                    = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let token_a_vault               = next_account_info(accounts_iter)?;
+    let token_b_vault               = next_account_info(accounts_iter)?;
+    let payer_token_a               = next_account_info(accounts_iter)?;
+    let payer_token_b               = next_account_info(accounts_iter)?;
+    let token_a_program             = next_account_info(accounts_iter)?;
+    let token_b_program             = next_account_info(accounts_iter)?;
+    let token_2022_program          = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let meteora_program             = next_account_info(accounts_iter)?;
+    let event_authority             = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_meteora_program(meteora_program)?;
+    Check::check_is_signer(payer)?;
+    Check::check_is_signer(position_nft_mint)?;
+    Check::check_is_signer(pool_creator_authority)?;
+    Check::check_any_token_program(token_a_program)?;
+    Check::check_any_token_program(token_b_program)?;
+    Check::check_token_2022_program(token_2022_program)?;
+    Check::check_system_program(system_program)?;
+    Check::check_pda(pool, &Pda::pool(config.key, token_a_mint.key, token_b_mint.key, meteora_program.key))?;
+    Check::check_pda(position, &Pda::position(position_nft_mint.key, meteora_program.key))?;
+    Check::check_pda(position_nft_account, &Pda::position_nft_account(position_nft_mint.key, meteora_program.key))?;
+    Check::check_token_vaults(token_a_vault, token_b_vault, token_a_mint, token_b_mint, pool, meteora_program.key)?;
+    Check::check_pool_authority(pool_authority)?;
+    Check::check_event_authority(event_authority, meteora_program.key)?;
+
+    {
+        let config_data = config.try_borrow_data()?;
+
+        if config.owner != meteora_program.key
+            || config_data.len() < CONFIG_POOL_CREATOR_AUTHORITY_END
+            || config_data[..8] != CONFIG_DISCRIMINATOR
+        {
+            msg!("Config {} is not a Meteora config account", config.key);
+            return Err(MeteoraProxyError::InvalidConfigAccount.into());
+        }
+
+        let expected_authority = Pubkey::try_from(&config_data[CONFIG_POOL_CREATOR_AUTHORITY_BEGIN..CONFIG_POOL_CREATOR_AUTHORITY_END])
+            .map_err(|_| MeteoraProxyError::InvalidConfigAccount)?;
+
+        if expected_authority != *pool_creator_authority.key {
+            msg!("Pool creator authority mismatch: expected {}, got {}", expected_authority, pool_creator_authority.key);
+            return Err(MeteoraProxyError::PoolCreatorAuthorityMismatch.into());
+        }
+    }
+
+    if let Err(reason) = params.validate() {
+        msg!("Invalid dynamic config pool parameters: {}", reason);
+        return Err(MeteoraProxyError::InvalidPoolParameters.into());
+    }
+
+    // The instruction discriminator for `initialize_pool_with_dynamic_config`
+    let mut instruction_data_cpi = vec![149,82,72,197,253,252,68,15];
+    instruction_data_cpi.extend_from_slice(&params.try_to_vec().unwrap());
+
+    // --- Construct CPI Accounts ---
+    // The order must match Meteora's `initialize_pool_with_dynamic_config` instruction.
+    let account_metas = vec![
+        AccountMeta::new_readonly(*creator.key, false),
+        AccountMeta::new(*position_nft_mint.key, true),
+        AccountMeta::new(*position_nft_account.key, false),
+        AccountMeta::new(*payer.key, true),
+        AccountMeta::new_readonly(*pool_creator_authority.key, true),
+        AccountMeta::new_readonly(*config.key, false),
+        AccountMeta::new_readonly(*pool_authority.key, false),
         AccountMeta::new(*pool.key, false),
-        AccountMeta::new(*input_token_account.key, false),
-        AccountMeta::new(*output_token_account.key, false),
-        AccountMeta::new(*token_a_vault.key, false),
-        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new(*position.key, false),
         AccountMeta::new_readonly(*token_a_mint.key, false),
         AccountMeta::new_readonly(*token_b_mint.key, false),
-        AccountMeta::new_readonly(*payer.key, true),
+        AccountMeta::new(*token_a_vault.key, false),
+        AccountMeta::new(*token_b_vault.key, false),
+        AccountMeta::new(*payer_token_a.key, false),
+        AccountMeta::new(*payer_token_b.key, false),
         AccountMeta::new_readonly(*token_a_program.key, false),
         AccountMeta::new_readonly(*token_b_program.key, false),
-        // referral account 暂时跳过
-        AccountMeta::new(*referral_token_account.key, false),
+        AccountMeta::new_readonly(*token_2022_program.key, false),
+        AccountMeta::new_readonly(*system_program.key, false),
         AccountMeta::new_readonly(*event_authority.key, false),
-        AccountMeta::new_readonly(*meteora_program.key, false),
+        AccountMeta::new_readonly(*meteora_program.key, false)
     ];
 
     // --- Create and Invoke CPI ---
@@ -291,30 +3106,473 @@ fn cpi_swap(
     };
 
     let account_infos = &[
+        creator.clone(),
+        position_nft_mint.clone(),
+        position_nft_account.clone(),
+        payer.clone(),
+        pool_creator_authority.clone(),
+        config.clone(),
         pool_authority.clone(),
         pool.clone(),
-        input_token_account.clone(),
-        output_token_account.clone(),
-        token_a_vault.clone(),
-        token_b_vault.clone(),
+        position.clone(),
         token_a_mint.clone(),
         token_b_mint.clone(),
-        payer.clone(),
+        token_a_vault.clone(),
+        token_b_vault.clone(),
+        payer_token_a.clone(),
+        payer_token_b.clone(),
         token_a_program.clone(),
         token_b_program.clone(),
-        referral_token_account.clone(), 
+        token_2022_program.clone(),
+        system_program.clone(),
         event_authority.clone(),
         meteora_program.clone(), // The program being called must be in account_infos
     ];
 
-    msg!("Invoking Meteora DAMM program to swap...");
+    msg!("Invoking Meteora DAMM program to initialize pool with dynamic config...");
 
     invoke(
-        &cpi_instruction, 
+        &cpi_instruction,
         account_infos
     )?;
-    
-    msg!("Swap executed successfully via CPI");
+
+    msg!("Pool with dynamic config initialized successfully via CPI");
+
+    Ok(())
+}
+
+
+
+/// Creates the proxy config PDA. The signer becomes the admin.
+///
+/// Only the upgrade authority of this program may create the config, so it cannot be taken
+/// over between deployment and initialization. The proxy starts unpaused and allowed to
+/// call the Meteora program of this build.
+fn initialize_config<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    config: &AccountInfo<'info>,
+    fee_bps: u16,
+    fee_recipient: Pubkey,
+) -> ProgramResult {
+
+    msg!("fee bps : {}", fee_bps);
+    msg!("fee recipient : {}", fee_recipient);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+    let program_data                = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    Check::check_upgrade_authority(program_id, program_data, admin)?;
+    Check::check_system_program(system_program)?;
+    ProxyConfig::validate_fee_bps(fee_bps)?;
+
+    let (config_key, bump) = Pda::config(program_id);
+    Check::check_pda(config, &config_key)?;
+
+    if config.owner == program_id {
+        msg!("Config {} is already initialized", config.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        program_id,
+        admin,
+        config,
+        system_program,
+        ProxyConfig::LEN,
+        &[CONFIG_PREFIX, &[bump]],
+    )?;
+
+    let proxy_config = ProxyConfig {
+        admin: *admin.key,
+        pending_admin: Pubkey::default(),
+        paused: false,
+        fee_bps,
+        fee_recipient,
+        meteora_program: program_id::METEORA_PROGRAM_ID,
+        bump,
+    };
+
+    proxy_config.save(config)?;
+
+    msg!("Config initialized successfully");
+
+    Ok(())
+}
+
+
+
+/// Sends the whole fee vault balance of one mint to a token account of the fee recipient.
+fn collect_proxy_fees<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    config: &AccountInfo<'info>,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+    let fee_vault                   = next_account_info(accounts_iter)?;
+    let recipient_token_account     = next_account_info(accounts_iter)?;
+    let token_mint                  = next_account_info(accounts_iter)?;
+    let token_program               = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    let proxy_config = ProxyConfig::load(program_id, config)?;
+    proxy_config.check_admin(admin)?;
+
+    Check::check_any_token_program(token_program)?;
+
+    if fee_vault.key != &Pda::fee_vault(config.key, token_mint.key, token_program.key) {
+        msg!("Fee vault {} is not the config token account of {}", fee_vault.key, token_mint.key);
+        return Err(MeteoraProxyError::InvalidFeeVault.into());
+    }
+
+    if TokenAccount::owner(recipient_token_account)? != proxy_config.fee_recipient {
+        msg!("Token account {} is not owned by the fee recipient", recipient_token_account.key);
+        return Err(MeteoraProxyError::InvalidTokenAccount.into());
+    }
+
+    let amount = TokenAccount::amount(fee_vault)?;
+    msg!("collected fees : {}", amount);
+
+    if amount > 0 {
+        TokenAccount::transfer(
+            token_program,
+            fee_vault,
+            token_mint,
+            recipient_token_account,
+            config,
+            amount,
+            &[&[CONFIG_PREFIX, &[proxy_config.bump]]],
+        )?;
+    }
+
+    msg!("Proxy fees collected successfully");
+
+    Ok(())
+}
+
+
+
+/// Changes the pause switch, the fee settings or the allowed Meteora program.
+fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    update: ConfigUpdate,
+) -> ProgramResult {
+
+    msg!("update : {:?}", update);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    let mut proxy_config = ProxyConfig::load(program_id, config)?;
+    proxy_config.check_admin(admin)?;
+
+    proxy_config.apply(update)?;
+    proxy_config.save(config)?;
+
+    msg!("Config updated successfully");
+
+    Ok(())
+}
+
+
+
+/// Proposes `new_admin` as the next admin.
+///
+/// The current admin stays in charge until `new_admin` signs `AcceptAdmin`, so a wrong key
+/// cannot lock the config. Proposing again replaces the pending admin.
+fn transfer_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+    new_admin: Pubkey,
+) -> ProgramResult {
+
+    msg!("new admin : {}", new_admin);
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    let mut proxy_config = ProxyConfig::load(program_id, config)?;
+    proxy_config.check_admin(admin)?;
+
+    if new_admin == Pubkey::default() {
+        msg!("New admin must not be the default public key");
+        return Err(MeteoraProxyError::Unauthorized.into());
+    }
+
+    proxy_config.pending_admin = new_admin;
+    proxy_config.save(config)?;
+
+    msg!("Admin transfer proposed successfully");
+
+    Ok(())
+}
+
+
+
+/// Completes an admin transfer: the pending admin signs and becomes the admin.
+fn accept_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let new_admin                   = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    let mut proxy_config = ProxyConfig::load(program_id, config)?;
+
+    if !new_admin.is_signer || proxy_config.pending_admin == Pubkey::default() || new_admin.key != &proxy_config.pending_admin {
+        msg!("{} is not the pending admin", new_admin.key);
+        return Err(MeteoraProxyError::Unauthorized.into());
+    }
+
+    proxy_config.admin = proxy_config.pending_admin;
+    proxy_config.pending_admin = Pubkey::default();
+    proxy_config.save(config)?;
+
+    msg!("Admin transferred successfully");
+
+    Ok(())
+}
+
+
+
+/// Creates the allowlist marker of a Meteora pool, so the proxy routes through it.
+///
+/// The pool may not exist yet: the admin derives the address of a launch pool from its
+/// config and mints and allows it before `CpiInitializePoolAndSwap` creates it. Either way
+/// the pool must be the Meteora pool PDA of the passed config and mints, or the customizable
+/// pool PDA of the mints.
+fn allow_pool<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    config: &AccountInfo,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let pool_config                 = next_account_info(accounts_iter)?;
+    let token_a_mint                = next_account_info(accounts_iter)?;
+    let token_b_mint                = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+    let system_program              = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    ProxyConfig::load(program_id, config)?.check_admin(admin)?;
+    Check::check_system_program(system_program)?;
+
+    let derived_pool = Pda::pool(pool_config.key, token_a_mint.key, token_b_mint.key, &program_id::METEORA_PROGRAM_ID);
+    let customizable_pool = Pda::customizable_pool(token_a_mint.key, token_b_mint.key, &program_id::METEORA_PROGRAM_ID);
+
+    if pool.key != &derived_pool && pool.key != &customizable_pool {
+        msg!("Pool {} is not the Meteora pool of config {} and its mints", pool.key, pool_config.key);
+        return Err(MeteoraProxyError::InvalidPda.into());
+    }
+
+    let not_created = solana_program::system_program::check_id(pool.owner) && pool.data_is_empty();
+
+    if pool.owner != &program_id::METEORA_PROGRAM_ID && !not_created {
+        msg!("Pool {} is neither a Meteora pool nor an unused address", pool.key);
+        return Err(MeteoraProxyError::InvalidPoolAccount.into());
+    }
+
+    let (allowed_pool_key, bump) = Pda::allowed_pool(pool.key, program_id);
+    Check::check_pda(allowed_pool, &allowed_pool_key)?;
+
+    if allowed_pool.owner == program_id {
+        msg!("Pool {} is already allowed", pool.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        program_id,
+        admin,
+        allowed_pool,
+        system_program,
+        AllowedPool::LEN,
+        &[ALLOWED_POOL_PREFIX, pool.key.as_ref(), &[bump]],
+    )?;
+
+    let marker = AllowedPool {
+        pool: *pool.key,
+        bump,
+    };
+
+    marker.serialize(&mut &mut allowed_pool.try_borrow_mut_data()?[..])?;
+
+    msg!("Pool {} allowed", pool.key);
 
     Ok(())
 }
+
+
+
+/// Closes the allowlist marker of a pool and returns its rent to the admin.
+fn disallow_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &AccountInfo,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    // The order of accounts must match the client-side order exactly.
+    let admin                       = next_account_info(accounts_iter)?;
+    let pool                        = next_account_info(accounts_iter)?;
+    let allowed_pool                = next_account_info(accounts_iter)?;
+
+
+    // --- Validation Checks ---
+    ProxyConfig::load(program_id, config)?.check_admin(admin)?;
+    Check::check_allowed_pool(program_id, allowed_pool, pool)?;
+
+    let rent = allowed_pool.lamports();
+    **allowed_pool.try_borrow_mut_lamports()? = 0;
+    **admin.try_borrow_mut_lamports()? = admin
+        .lamports()
+        .checked_add(rent)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    allowed_pool.realloc(0, false)?;
+    allowed_pool.assign(&solana_program::system_program::ID);
+
+    msg!("Pool {} disallowed", pool.key);
+
+    Ok(())
+}
+
+
+
+/// Creates a rent-exempt PDA owned by this program.
+///
+/// `create_account` fails on an address that already holds lamports, so a pre-funded PDA
+/// is topped up to rent exemption, then allocated and assigned instead.
+fn create_pda_account<'info>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, rent, space as u64, program_id),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+
+
+/// Splits the operator off the end of a treasury instruction's accounts.
+///
+/// Treasury instructions spend protocol-owned funds, so the operator must be the config
+/// admin and sign. Returns the remaining accounts for the handler.
+fn treasury_accounts<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    config: &AccountInfo,
+) -> Result<&'a [AccountInfo<'info>], ProgramError> {
+
+    let (operator, accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    ProxyConfig::load(program_id, config)?.check_admin(operator)?;
+
+    Ok(accounts)
+}
+
+
+
+/// Reads liquidity and sqrt price from a Meteora pool account.
+fn read_pool(pool: &AccountInfo) -> Result<MeteoraDammV2Pool, ProgramError> {
+    MeteoraDammV2Pool::from_account_data(&pool.try_borrow_data()?)
+        .ok_or_else(|| MeteoraProxyError::InvalidPoolAccount.into())
+}
+
+
+
+/// Reads the base fee settings of a Meteora pool account.
+///
+/// Fails for dynamic fee pools, which `quote` cannot price.
+fn read_pool_fee(pool: &AccountInfo) -> Result<MeteoraDammV2PoolFee, ProgramError> {
+    let pool_fee = MeteoraDammV2PoolFee::from_account_data(&pool.try_borrow_data()?)
+        .ok_or(MeteoraProxyError::InvalidPoolAccount)?;
+
+    if !pool_fee.is_supported() {
+        msg!("Pool {} has a dynamic fee, which the proxy cannot quote", pool.key);
+        return Err(MeteoraProxyError::DynamicFeeNotSupported.into());
+    }
+
+    Ok(pool_fee)
+}
+
+
+
+/// Reads the sqrt min and max price of a Meteora pool account.
+fn read_pool_price_range(pool: &AccountInfo) -> Result<(u128, u128), ProgramError> {
+    MeteoraDammV2Pool::price_range_from_account_data(&pool.try_borrow_data()?)
+        .ok_or_else(|| MeteoraProxyError::InvalidPoolAccount.into())
+}
+
+
+
+/// Reads the cumulated fee metrics of a Meteora pool account.
+fn read_pool_total_fee(pool: &AccountInfo) -> Result<u128, ProgramError> {
+    MeteoraDammV2Pool::total_fee_from_account_data(&pool.try_borrow_data()?)
+        .ok_or_else(|| MeteoraProxyError::InvalidPoolAccount.into())
+}
@@ -1,10 +1,19 @@
 use borsh::{BorshDeserialize,BorshSerialize};
 use ruint::aliases::U256;
 
+use crate::return_data::QuoteResult;
+
 // expected total data bytes
 const EXPECT_TOTAL_BYTES: usize = 1112;
 const DISCRIMINATOR: usize = 8;
 
+// token mints and vaults bytes ranges
+pub const TOKEN_A_MINT_BEGIN: usize = 160 + DISCRIMINATOR;
+pub const TOKEN_B_MINT_BEGIN: usize = TOKEN_A_MINT_BEGIN + 32;
+pub const TOKEN_A_VAULT_BEGIN: usize = TOKEN_B_MINT_BEGIN + 32;
+pub const TOKEN_B_VAULT_BEGIN: usize = TOKEN_A_VAULT_BEGIN + 32;
+pub const TOKEN_B_VAULT_END: usize = TOKEN_B_VAULT_BEGIN + 32;
+
 // liquidity bytes range
 pub const LIQUIDITY_BEGIN: usize = 352 + DISCRIMINATOR;
 pub const LIQUIDITY_END: usize = LIQUIDITY_BEGIN + 16;
@@ -13,14 +22,71 @@ pub const LIQUIDITY_END: usize = LIQUIDITY_BEGIN + 16;
 pub const SQRT_PRICE_BEGIN: usize = 448 + DISCRIMINATOR;
 pub const SQRT_PRICE_END: usize = SQRT_PRICE_BEGIN + 16;
 
+// sqrt price range of the pool liquidity
+pub const SQRT_MIN_PRICE_BEGIN: usize = 416 + DISCRIMINATOR;
+pub const SQRT_MAX_PRICE_BEGIN: usize = 432 + DISCRIMINATOR;
 
-#[derive(BorshSerialize,BorshDeserialize)]
+// fee metrics bytes ranges
+// total_lp_a_fee, total_lp_b_fee (u128) then protocol / partner a and b fees (u64)
+pub const TOTAL_LP_A_FEE_BEGIN: usize = 560 + DISCRIMINATOR;
+pub const TOTAL_LP_B_FEE_BEGIN: usize = TOTAL_LP_A_FEE_BEGIN + 16;
+pub const TOTAL_PROTOCOL_A_FEE_BEGIN: usize = TOTAL_LP_B_FEE_BEGIN + 16;
+pub const TOTAL_PARTNER_B_FEE_END: usize = TOTAL_PROTOCOL_A_FEE_BEGIN + 32;
+
+// sqrt price bounds accepted by cp-amm
+pub const MIN_SQRT_PRICE: u128 = 4295048016;
+pub const MAX_SQRT_PRICE: u128 = 79226673521066979257578248091;
+
+// activation type byte (0 = slot, 1 = timestamp)
+pub const ACTIVATION_TYPE_BEGIN: usize = 472 + DISCRIMINATOR;
+
+// pool fees: base fee cliff numerator (first field of the pool) and collect fee mode
+pub const CLIFF_FEE_NUMERATOR_BEGIN: usize = DISCRIMINATOR;
+pub const COLLECT_FEE_MODE_BEGIN: usize = ACTIVATION_TYPE_BEGIN + 4;
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+pub const COLLECT_FEE_MODE_ONLY_B: u8 = 1;
+
+// dynamic fee `initialized` flag, after the 40 byte base fee and the fee percents
+pub const DYNAMIC_FEE_INITIALIZED_BEGIN: usize = 48 + DISCRIMINATOR;
+
+// config account: anchor discriminator and pool_creator_authority bytes range
+pub const CONFIG_DISCRIMINATOR: [u8; DISCRIMINATOR] = [155, 12, 170, 224, 30, 250, 204, 130];
+pub const CONFIG_POOL_CREATOR_AUTHORITY_BEGIN: usize = 32 + DISCRIMINATOR;
+pub const CONFIG_POOL_CREATOR_AUTHORITY_END: usize = CONFIG_POOL_CREATOR_AUTHORITY_BEGIN + 32;
+
+// number of reward slots supported by a pool
+pub const NUM_REWARDS: usize = 2;
+
+// vesting limits, mirrored from cp-amm `activation`
+pub const MAX_VESTING_SLOT_DURATION: u64 = 9000 * 24 * 365 * 10; // 10 years
+pub const MAX_VESTING_TIME_DURATION: u64 = 3600 * 24 * 365 * 10; // 10 years
+
+
+#[derive(BorshSerialize,BorshDeserialize,Debug,Clone,Copy,PartialEq,Eq)]
 pub enum  TradeDirection{
 
     BUY,
     SELL,
 }
 
+impl TradeDirection {
+
+    // BUY spends token A for token B, SELL spends token B for token A
+    pub fn is_a_to_b(self) -> bool {
+        matches!(self, TradeDirection::BUY)
+    }
+}
+
+
+// swap2 mode, serialized as the u8 cp-amm expects
+#[derive(BorshSerialize,BorshDeserialize,Debug,Clone,Copy,PartialEq,Eq)]
+pub enum SwapMode {
+
+    ExactIn,
+    PartialFill,
+    ExactOut,
+}
+
 
 // initialize_pool
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -31,6 +97,185 @@ pub struct InitializePoolParameters {
 }
 
 
+// initialize_customizable_pool
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BaseFeeParameters {
+    pub cliff_fee_numerator: u64,
+    pub number_of_period: u16,
+    pub period_frequency: u64,
+    pub reduction_factor: u64,
+    pub fee_scheduler_mode: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct DynamicFeeParameters {
+    pub bin_step: u16,
+    pub bin_step_u128: u128,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PoolFeeParameters {
+    pub base_fee: BaseFeeParameters,
+    pub padding: [u8; 3],
+    pub dynamic_fee: Option<DynamicFeeParameters>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct InitializeCustomizablePoolParameters {
+    pub pool_fees: PoolFeeParameters,
+    pub sqrt_min_price: u128,
+    pub sqrt_max_price: u128,
+    pub has_alpha_vault: bool,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub activation_type: u8,
+    pub collect_fee_mode: u8,
+    pub activation_point: Option<u64>,
+}
+
+impl InitializeCustomizablePoolParameters {
+
+    /// Checks the price range and enum values before handing them to cp-amm.
+    pub fn validate(&self) -> Result<(), &'static str> {
+
+        if self.sqrt_min_price < MIN_SQRT_PRICE || self.sqrt_max_price > MAX_SQRT_PRICE {
+            return Err("sqrt price range is outside of the supported bounds");
+        }
+
+        if self.sqrt_min_price >= self.sqrt_max_price {
+            return Err("sqrt min price must be lower than sqrt max price");
+        }
+
+        if self.sqrt_price < self.sqrt_min_price || self.sqrt_price > self.sqrt_max_price {
+            return Err("sqrt price is outside of the price range");
+        }
+
+        if self.liquidity == 0 {
+            return Err("liquidity must be greater than zero");
+        }
+
+        // 0 = slot, 1 = timestamp
+        if self.activation_type > 1 {
+            return Err("unknown activation type");
+        }
+
+        // 0 = both tokens, 1 = only token b
+        if self.collect_fee_mode > 1 {
+            return Err("unknown collect fee mode");
+        }
+
+        Ok(())
+    }
+}
+
+
+// add_liquidity
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AddLiquidityParameters {
+    pub liquidity_delta: u128,
+    pub token_a_amount_threshold: u64,
+    pub token_b_amount_threshold: u64,
+}
+
+
+// remove_liquidity
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RemoveLiquidityParameters {
+    pub liquidity_delta: u128,
+    pub token_a_amount_threshold: u64,
+    pub token_b_amount_threshold: u64,
+}
+
+
+// remove_all_liquidity
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RemoveAllLiquidityParameters {
+    pub token_a_amount_threshold: u64,
+    pub token_b_amount_threshold: u64,
+}
+
+
+// split_position
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SplitPositionParameters {
+    pub unlocked_liquidity_percentage: u8,
+    pub permanent_locked_liquidity_percentage: u8,
+    pub fee_a_percentage: u8,
+    pub fee_b_percentage: u8,
+    pub reward_0_percentage: u8,
+    pub reward_1_percentage: u8,
+    pub padding: [u8; 16],
+}
+
+
+// lock_position
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VestingParameters {
+    pub cliff_point: Option<u64>,
+    pub period_frequency: u64,
+    pub cliff_unlock_liquidity: u128,
+    pub liquidity_per_period: u128,
+    pub number_of_period: u16,
+}
+
+impl VestingParameters {
+
+    /// Mirrors the cp-amm vesting validation so bad parameters fail before the CPI.
+    ///
+    /// `current_point` is the current slot or timestamp depending on the pool activation
+    /// type, and `max_vesting_duration` the matching `MAX_VESTING_*_DURATION` limit.
+    pub fn validate(&self, current_point: u64, max_vesting_duration: u64) -> Result<(), &'static str> {
+
+        let cliff_point = self.cliff_point.unwrap_or(current_point);
+
+        if cliff_point < current_point {
+            return Err("cliff point is in the past");
+        }
+
+        if self.number_of_period > 0 && (self.period_frequency == 0 || self.liquidity_per_period == 0) {
+            return Err("period frequency and liquidity per period must be set when periods are used");
+        }
+
+        let vesting_duration = self.period_frequency
+            .checked_mul(self.number_of_period.into())
+            .and_then(|periods| periods.checked_add(cliff_point - current_point))
+            .ok_or("vesting duration overflow")?;
+
+        if vesting_duration > max_vesting_duration {
+            return Err("vesting duration exceeds the maximum");
+        }
+
+        let total_lock_liquidity = self.liquidity_per_period
+            .checked_mul(self.number_of_period.into())
+            .and_then(|periods| periods.checked_add(self.cliff_unlock_liquidity))
+            .ok_or("total locked liquidity overflow")?;
+
+        if total_lock_liquidity == 0 {
+            return Err("nothing to lock");
+        }
+
+        Ok(())
+    }
+}
+
+
+
+// how the initial liquidity of a new pool is locked
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum InitialLock {
+
+    // all of it, forever
+    Permanent,
+    // released by a vesting schedule
+    Vesting(VestingParameters),
+}
+
+
 
 
 #[derive(BorshSerialize,BorshDeserialize)]
@@ -54,6 +299,297 @@ impl MeteoraDammV2Pool {
             sqrt_price,
         }
     }
+
+    /// Parses a pool from raw account data, the on-chain counterpart of the client `get_pool`.
+    ///
+    /// Returns `None` when the data does not have the expected pool layout.
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+
+        if data.len() != EXPECT_TOTAL_BYTES {
+            return None;
+        }
+
+        Some(MeteoraDammV2Pool::new(
+            data.len(),
+            read_u128(data, LIQUIDITY_BEGIN)?,
+            read_u128(data, SQRT_PRICE_BEGIN)?,
+        ))
+    }
+
+    /// Sqrt min and max price the pool liquidity is spread over.
+    pub fn price_range_from_account_data(data: &[u8]) -> Option<(u128, u128)> {
+
+        Some((
+            read_u128(data, SQRT_MIN_PRICE_BEGIN)?,
+            read_u128(data, SQRT_MAX_PRICE_BEGIN)?,
+        ))
+    }
+
+    /// Sum of every fee the pool ever collected (LP, protocol and partner, token A and B).
+    ///
+    /// The difference of two snapshots is the fee charged in between. A swap only
+    /// charges fees in one token, so the sum never mixes units.
+    pub fn total_fee_from_account_data(data: &[u8]) -> Option<u128> {
+
+        let lp_fee = read_u128(data, TOTAL_LP_A_FEE_BEGIN)?
+            .checked_add(read_u128(data, TOTAL_LP_B_FEE_BEGIN)?)?;
+
+        let other_fees = data
+            .get(TOTAL_PROTOCOL_A_FEE_BEGIN..TOTAL_PARTNER_B_FEE_END)?
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()) as u128)
+            .sum::<u128>();
+
+        lp_fee.checked_add(other_fees)
+    }
+}
+
+
+
+// base fee of a pool
+// cliff_fee_numerator is the fee before any fee scheduler reduction, so quotes never under-estimate
+// the base fee; the variable fee of dynamic fee pools is not modelled, see `is_supported`
+#[derive(BorshSerialize,BorshDeserialize,Debug,Clone,Copy)]
+pub struct MeteoraDammV2PoolFee {
+
+    pub cliff_fee_numerator: u64,
+    pub collect_fee_mode: u8,
+    pub dynamic_fee: bool,
+}
+
+impl MeteoraDammV2PoolFee {
+
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+
+        if data.len() != EXPECT_TOTAL_BYTES {
+            return None;
+        }
+
+        let numerator: [u8; 8] = data
+            .get(CLIFF_FEE_NUMERATOR_BEGIN..CLIFF_FEE_NUMERATOR_BEGIN + 8)?
+            .try_into()
+            .ok()?;
+
+        Some(MeteoraDammV2PoolFee {
+            cliff_fee_numerator: u64::from_le_bytes(numerator),
+            collect_fee_mode: *data.get(COLLECT_FEE_MODE_BEGIN)?,
+            dynamic_fee: *data.get(DYNAMIC_FEE_INITIALIZED_BEGIN)? != 0,
+        })
+    }
+
+    // `quote` only charges the base fee, so it would under-estimate the fee of dynamic fee pools
+    pub fn is_supported(&self) -> bool {
+        !self.dynamic_fee
+    }
+
+    // OnlyB pools take the fee from token B: the input of a B to A swap, the output otherwise
+    pub fn fee_on_input(&self, direction: bool) -> bool {
+        self.collect_fee_mode == COLLECT_FEE_MODE_ONLY_B && !direction
+    }
+
+    // rounded up like the cp-amm `get_fee_on_amount`
+    pub fn fee_on_amount(&self, amount: u64) -> u64 {
+        let fee = (amount as u128 * self.cliff_fee_numerator as u128).div_ceil(FEE_DENOMINATOR as u128);
+        fee.min(amount as u128) as u64
+    }
+}
+
+
+
+/// Quotes a swap against a pool without executing it.
+///
+/// Uses the same curve math as `MeteoraDammV2PoolSwapParams::new` and charges the base fee
+/// on the side the pool collects it. `price_impact_bps` compares the pre-fee output with the
+/// output at the current spot price. Dynamic fee pools are not supported: their variable fee
+/// is left out, so check `MeteoraDammV2PoolFee::is_supported` first. Returns `None` when
+/// the pool math overflows.
+pub fn quote(pool: &MeteoraDammV2Pool, fee: &MeteoraDammV2PoolFee, direction: bool, amount_in: u64) -> Option<QuoteResult> {
+
+    let (amount_out, fee_amount, price_impact_bps) = if fee.fee_on_input(direction) {
+        let fee_amount = fee.fee_on_amount(amount_in);
+        let gross_out = MeteoraDammV2PoolSwapParams::amount_out(direction, pool.liquidity, pool.sqrt_price, amount_in - fee_amount)?;
+        let impact = price_impact_bps(direction, pool.sqrt_price, amount_in - fee_amount, gross_out);
+        (gross_out, fee_amount, impact)
+    } else {
+        let gross_out = MeteoraDammV2PoolSwapParams::amount_out(direction, pool.liquidity, pool.sqrt_price, amount_in)?;
+        let fee_amount = fee.fee_on_amount(gross_out);
+        let impact = price_impact_bps(direction, pool.sqrt_price, amount_in, gross_out);
+        (gross_out - fee_amount, fee_amount, impact)
+    };
+
+    Some(QuoteResult {
+        amount_in,
+        amount_out,
+        fee: fee_amount,
+        price_impact_bps,
+        sqrt_price: pool.sqrt_price,
+    })
+}
+
+// number of chunks the split optimizer hands out
+pub const SPLIT_STEPS: u64 = 100;
+
+/// Splits `amount_in` across pools of the same pair to maximize the total output.
+///
+/// Greedy: hands out `amount_in` in `SPLIT_STEPS` chunks, each to the pool with the best
+/// marginal quote. Constant product outputs are concave, so this converges to the best
+/// split as the chunks shrink. Returns one amount per pool in input order; pools with a
+/// zero amount must be left out of the `CpiSplitSwap` legs. Returns `None` when the pool
+/// math overflows for one of the pools.
+pub fn optimal_split(pools: &[(MeteoraDammV2Pool, MeteoraDammV2PoolFee)], direction: bool, amount_in: u64) -> Option<Vec<u64>> {
+
+    let mut amounts = vec![0u64; pools.len()];
+
+    if pools.is_empty() || amount_in == 0 {
+        return Some(amounts);
+    }
+
+    let chunk = (amount_in / SPLIT_STEPS).max(1);
+    let mut remaining = amount_in;
+
+    while remaining > 0 {
+
+        // the last chunk also takes the rounding remainder
+        let step = if remaining < 2 * chunk { remaining } else { chunk };
+
+        let marginal_outs = pools
+            .iter()
+            .zip(amounts.iter())
+            .map(|((pool, fee), allocated)| {
+                let before = quote(pool, fee, direction, *allocated)?.amount_out;
+                let after = quote(pool, fee, direction, allocated + step)?.amount_out;
+                Some(after.saturating_sub(before))
+            })
+            .collect::<Option<Vec<u64>>>()?;
+
+        let best = marginal_outs
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, marginal_out)| **marginal_out)
+            .map(|(index, _)| index)
+            .unwrap();
+
+        amounts[best] += step;
+        remaining -= step;
+    }
+
+    Some(amounts)
+}
+
+// liquidity token A can back between sqrt_price and sqrt_max_price
+// inverse of the cp-amm `get_delta_amount_a_unsigned`: amount * sqrt_price * sqrt_max_price / (sqrt_max_price - sqrt_price)
+pub fn liquidity_from_amount_a(amount: u64, sqrt_price: u128, sqrt_max_price: u128) -> u128 {
+
+    if sqrt_price >= sqrt_max_price {
+        return u128::MAX;
+    }
+
+    let Some(numerator) = (U256::from(amount) * U256::from(sqrt_price)).checked_mul(U256::from(sqrt_max_price)) else {
+        return u128::MAX;
+    };
+
+    let result: U256 = numerator / U256::from(sqrt_max_price - sqrt_price);
+    result.try_into().unwrap_or(u128::MAX)
+}
+
+// liquidity token B can back between sqrt_min_price and sqrt_price
+// inverse of the cp-amm `get_delta_amount_b_unsigned`: (amount << 128) / (sqrt_price - sqrt_min_price)
+pub fn liquidity_from_amount_b(amount: u64, sqrt_price: u128, sqrt_min_price: u128) -> u128 {
+
+    if sqrt_price <= sqrt_min_price {
+        return u128::MAX;
+    }
+
+    let result: U256 = (U256::from(amount) << 128) / U256::from(sqrt_price - sqrt_min_price);
+    result.try_into().unwrap_or(u128::MAX)
+}
+
+/// Amount of the input token to swap before a single-token deposit.
+///
+/// Binary searches the swap amount for which the remaining input and the swap output back
+/// the same liquidity at the post-swap price, so the deposit leaves as little dust as
+/// possible. Only the swap amount net of the `proxy_fee_bps` proxy fee reaches the pool.
+/// `direction` is true when the input is token A. Returns `None` when the pool math
+/// overflows.
+pub fn zap_in_swap_amount(
+    pool: &MeteoraDammV2Pool,
+    fee: &MeteoraDammV2PoolFee,
+    sqrt_min_price: u128,
+    sqrt_max_price: u128,
+    direction: bool,
+    amount_in: u64,
+    proxy_fee_bps: u16,
+) -> Option<u64> {
+
+    let mut low = 0u64;
+    let mut high = amount_in;
+
+    while low < high {
+
+        let swap_amount = low + (high - low) / 2;
+
+        // the proxy fee is skimmed before the swap, rounded down like `ProxyConfig::fee_on`
+        let pool_amount = swap_amount - (swap_amount as u128 * proxy_fee_bps as u128 / 10_000) as u64;
+
+        let amount_out = quote(pool, fee, direction, pool_amount)?.amount_out;
+        let next_sqrt_price = MeteoraDammV2PoolSwapParams::next_sqrt_price(direction, pool.liquidity, pool.sqrt_price, pool_amount)?;
+        let remaining = amount_in - swap_amount;
+
+        let (liquidity_in, liquidity_out) = if direction {
+            (
+                liquidity_from_amount_a(remaining, next_sqrt_price, sqrt_max_price),
+                liquidity_from_amount_b(amount_out, next_sqrt_price, sqrt_min_price),
+            )
+        } else {
+            (
+                liquidity_from_amount_b(remaining, next_sqrt_price, sqrt_min_price),
+                liquidity_from_amount_a(amount_out, next_sqrt_price, sqrt_max_price),
+            )
+        };
+
+        // too much input left over: swap more
+        if liquidity_in > liquidity_out {
+            low = swap_amount + 1;
+        } else {
+            high = swap_amount;
+        }
+    }
+
+    Some(low)
+}
+
+// spot price of A in B is sqrt_price^2 / 2^128
+fn price_impact_bps(direction: bool, sqrt_price: u128, amount_in: u64, amount_out: u64) -> u64 {
+
+    if sqrt_price == 0 || amount_in == 0 {
+        return 0;
+    }
+
+    let sqrt_price_256 = U256::from(sqrt_price);
+    let price_256 = sqrt_price_256 * sqrt_price_256;
+    let amount_in_256 = U256::from(amount_in);
+
+    let spot_out = if direction {
+        // shift in two steps so the product stays within 256 bits
+        ((price_256 >> 64) * amount_in_256) >> 64
+    } else {
+        (amount_in_256 << 128) / price_256
+    };
+
+    let amount_out_256 = U256::from(amount_out);
+    if spot_out == U256::ZERO || amount_out_256 >= spot_out {
+        return 0;
+    }
+
+    let impact: U256 = (spot_out - amount_out_256) * U256::from(10_000u64) / spot_out;
+    impact.try_into().unwrap_or(10_000u64)
+}
+
+
+
+fn read_u128(data: &[u8], begin: usize) -> Option<u128> {
+    let bytes: [u8; 16] = data.get(begin..begin + 16)?.try_into().ok()?;
+    Some(u128::from_le_bytes(bytes))
 }
 
 
@@ -66,13 +602,49 @@ pub struct MeteoraDammV2PoolSwapParams {
 }
 
 impl MeteoraDammV2PoolSwapParams {
+    // panics when the pool math overflows, use `amount_out` to handle it
     pub fn new(direction: bool, liquidity: u128, sqrt_price: u128, amount_in: u64, slipage_bps: u64) -> Self {
         
+        let amount_out = Self::amount_out(direction, liquidity, sqrt_price, amount_in)
+            .expect("swap amount overflows the pool math");
+
+        // slipage caculation
+        let minimum_amount_out = amount_out.saturating_mul(10_000 - slipage_bps) / 10_000;
+        
+        MeteoraDammV2PoolSwapParams {
+            amount_in,
+            minimum_amount_out,
+        }
+    }
+
+    // pool sqrt price after swapping amount_in, before fees, None when the pool math overflows
+    pub fn next_sqrt_price(direction: bool, liquidity: u128, sqrt_price: u128, amount_in: u64) -> Option<u128> {
+
         if liquidity == 0 || sqrt_price == 0 || amount_in == 0 {
-            return MeteoraDammV2PoolSwapParams {
-                amount_in,
-                minimum_amount_out: 0,
-            };
+            return Some(sqrt_price);
+        }
+
+        let liquidity_256 = U256::from(liquidity);
+        let sqrt_price_256 = U256::from(sqrt_price);
+        let amount_in_256 = U256::from(amount_in);
+
+        let next_sqrt_price: U256 = if direction {
+            // A to B: sqrt_price * liquidity / (liquidity + amount_in * sqrt_price)
+            let denominator = liquidity_256.checked_add(amount_in_256.checked_mul(sqrt_price_256)?)?;
+            liquidity_256.checked_mul(sqrt_price_256)? / denominator
+        } else {
+            // B to A: sqrt_price + (amount_in * 2^128) / liquidity
+            sqrt_price_256.checked_add((amount_in_256 << 128) / liquidity_256)?
+        };
+
+        next_sqrt_price.try_into().ok()
+    }
+
+    // expected output before fees and slippage, None when the pool math overflows
+    pub fn amount_out(direction: bool, liquidity: u128, sqrt_price: u128, amount_in: u64) -> Option<u64> {
+
+        if liquidity == 0 || sqrt_price == 0 || amount_in == 0 {
+            return Some(0);
         }
         
         // enhence to 256 bit to avoid overflow
@@ -80,54 +652,319 @@ impl MeteoraDammV2PoolSwapParams {
         let sqrt_price_256 = U256::from(sqrt_price);
         let amount_in_256 = U256::from(amount_in);
         
-        let amount_out = if direction {
+        if direction {
             // A to B:  get_next_sqrt_price_from_amount_a_rounding_up + get_delta_amount_b_unsigned
             
             // Step 1:  next_sqrt_price = sqrt_price * liquidity / (liquidity + amount_in * sqrt_price)
-            let product = amount_in_256 * sqrt_price_256;
-            let denominator = liquidity_256 + product;
+            let product = amount_in_256.checked_mul(sqrt_price_256)?;
+            let denominator = liquidity_256.checked_add(product)?;
+            let next_sqrt_price = liquidity_256.checked_mul(sqrt_price_256)? / denominator;
             
-            if denominator == U256::ZERO {
-                0
-            } else {
-                let next_sqrt_price = (liquidity_256 * sqrt_price_256) / denominator;
-                
-                // Step 2:  output = liquidity * (sqrt_price - next_sqrt_price) / 2^128
-                let price_diff = sqrt_price_256 - next_sqrt_price;
-                let numerator = liquidity_256 * price_diff;
-                // devide 2^128 (RESOLUTION * 2 = 64 * 2 = 128)
-                let result: ruint::Uint<256, 4> = numerator >> 128;
-                result.try_into().unwrap_or(0u64)
-            }
+            // Step 2:  output = liquidity * (sqrt_price - next_sqrt_price) / 2^128
+            let price_diff = sqrt_price_256 - next_sqrt_price;
+            let numerator = liquidity_256.checked_mul(price_diff)?;
+            // devide 2^128 (RESOLUTION * 2 = 64 * 2 = 128)
+            let result: ruint::Uint<256, 4> = numerator >> 128;
+            result.try_into().ok()
         } else {
             // B to A:  get_next_sqrt_price_from_amount_b_rounding_down + get_delta_amount_a_unsigned
             
             // Step 1:  next_sqrt_price = sqrt_price + (amount_in * 2^128) / liquidity
             let amount_shifted = amount_in_256 << 128; // 乘以 2^128
             let price_increase = amount_shifted / liquidity_256;
-            let next_sqrt_price = sqrt_price_256 + price_increase;
+            let next_sqrt_price = sqrt_price_256.checked_add(price_increase)?;
             
             // Step 2: output = liquidity * (next_sqrt_price - sqrt_price) / (sqrt_price * next_sqrt_price)
             let price_diff = next_sqrt_price - sqrt_price_256;
-            let numerator = liquidity_256 * price_diff;
-            let denominator = sqrt_price_256 * next_sqrt_price;
+            let numerator = liquidity_256.checked_mul(price_diff)?;
+            let denominator = sqrt_price_256.checked_mul(next_sqrt_price)?;
             
-            if denominator == U256::ZERO {
-                0
-            } else {
-                let result: ruint::Uint<256, 4> = numerator / denominator;
-                result.try_into().unwrap_or(0u64)
-            }
-        };
-        
-        // slipage caculation
-        let minimum_amount_out = amount_out.saturating_mul(10_000 - slipage_bps) / 10_000;
-        
-        MeteoraDammV2PoolSwapParams {
-            amount_in,
-            minimum_amount_out,
+            let result: ruint::Uint<256, 4> = numerator / denominator;
+            result.try_into().ok()
         }
     }
 }
 
 
+
+// swap2
+// ExactIn / PartialFill: amount_0 = amount in,  amount_1 = minimum amount out
+// ExactOut:              amount_0 = amount out, amount_1 = maximum amount in
+#[derive(BorshDeserialize,BorshSerialize)]
+pub struct MeteoraDammV2PoolSwap2Params {
+
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub swap_mode: SwapMode,
+}
+
+impl MeteoraDammV2PoolSwap2Params {
+
+    pub fn exact_in(amount_in: u64, minimum_amount_out: u64) -> Self {
+        MeteoraDammV2PoolSwap2Params {
+            amount_0: amount_in,
+            amount_1: minimum_amount_out,
+            swap_mode: SwapMode::ExactIn,
+        }
+    }
+
+    pub fn partial_fill(amount_in: u64, minimum_amount_out: u64) -> Self {
+        MeteoraDammV2PoolSwap2Params {
+            amount_0: amount_in,
+            amount_1: minimum_amount_out,
+            swap_mode: SwapMode::PartialFill,
+        }
+    }
+
+    pub fn exact_out(amount_out: u64, maximum_amount_in: u64) -> Self {
+        MeteoraDammV2PoolSwap2Params {
+            amount_0: amount_out,
+            amount_1: maximum_amount_in,
+            swap_mode: SwapMode::ExactOut,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // price 1, in Q64.64
+    const SQRT_PRICE_ONE: u128 = 1 << 64;
+
+    // 1%
+    const FEE_NUMERATOR: u64 = 10_000_000;
+
+    fn pool(liquidity: u128) -> MeteoraDammV2Pool {
+        MeteoraDammV2Pool::new(EXPECT_TOTAL_BYTES, liquidity, SQRT_PRICE_ONE)
+    }
+
+    fn fee(collect_fee_mode: u8) -> MeteoraDammV2PoolFee {
+        MeteoraDammV2PoolFee {
+            cliff_fee_numerator: FEE_NUMERATOR,
+            collect_fee_mode,
+            dynamic_fee: false,
+        }
+    }
+
+    #[test]
+    fn quote_both_tokens_charges_the_output() {
+        let pool = pool(1_000_000_000_000 << 64);
+        let fee = fee(0);
+
+        for direction in [true, false] {
+            let gross_out = MeteoraDammV2PoolSwapParams::amount_out(direction, pool.liquidity, pool.sqrt_price, 1_000_000).unwrap();
+            let result = quote(&pool, &fee, direction, 1_000_000).unwrap();
+
+            assert_eq!(result.fee, fee.fee_on_amount(gross_out));
+            assert_eq!(result.amount_out, gross_out - result.fee);
+        }
+    }
+
+    #[test]
+    fn quote_only_b_charges_token_b() {
+        let pool = pool(1_000_000_000_000 << 64);
+        let fee = fee(COLLECT_FEE_MODE_ONLY_B);
+
+        // B to A: token B is the input
+        let result = quote(&pool, &fee, false, 1_000_000).unwrap();
+        assert_eq!(result.fee, fee.fee_on_amount(1_000_000));
+        assert_eq!(
+            result.amount_out,
+            MeteoraDammV2PoolSwapParams::amount_out(false, pool.liquidity, pool.sqrt_price, 1_000_000 - result.fee).unwrap(),
+        );
+
+        // A to B: token B is the output
+        let gross_out = MeteoraDammV2PoolSwapParams::amount_out(true, pool.liquidity, pool.sqrt_price, 1_000_000).unwrap();
+        let result = quote(&pool, &fee, true, 1_000_000).unwrap();
+        assert_eq!(result.fee, fee.fee_on_amount(gross_out));
+        assert_eq!(result.amount_out, gross_out - result.fee);
+    }
+
+    #[test]
+    fn quote_is_below_the_slippage_free_output() {
+        let pool = pool(1_000_000_000_000 << 64);
+        let params = MeteoraDammV2PoolSwapParams::new(true, pool.liquidity, pool.sqrt_price, 1_000_000, 0);
+
+        assert!(quote(&pool, &fee(0), true, 1_000_000).unwrap().amount_out < params.minimum_amount_out);
+    }
+
+    #[test]
+    fn amount_out_overflow_is_not_a_zero_quote() {
+        // a thin pool at the top of the price range cannot price a full u64 input
+        assert_eq!(MeteoraDammV2PoolSwapParams::amount_out(false, 1, MAX_SQRT_PRICE, u64::MAX), None);
+        let pool = MeteoraDammV2Pool::new(EXPECT_TOTAL_BYTES, 1, MAX_SQRT_PRICE);
+        assert_eq!(quote(&pool, &fee(0), false, u64::MAX).map(|result| result.amount_out), None);
+    }
+
+    #[test]
+    fn split_sums_to_amount_in_and_favours_the_deeper_pool() {
+        let pools = [
+            (pool(1_000_000_000 << 64), fee(0)),
+            (pool(10_000_000_000 << 64), fee(0)),
+        ];
+
+        let amounts = optimal_split(&pools, true, 123_456_789).unwrap();
+
+        assert_eq!(amounts.iter().sum::<u64>(), 123_456_789);
+        assert!(amounts[1] > amounts[0]);
+    }
+
+    #[test]
+    fn split_of_nothing_is_empty() {
+        let pools = [(pool(1_000_000_000 << 64), fee(0))];
+
+        assert_eq!(optimal_split(&pools, true, 0).unwrap(), vec![0]);
+        assert!(optimal_split(&[], true, 1_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn zap_in_leaves_balanced_liquidity() {
+        let pool = pool(1_000_000_000_000 << 64);
+        let fee = fee(0);
+
+        for (direction, proxy_fee_bps) in [(true, 0), (false, 0), (true, 100), (false, 100)] {
+            let amount_in = 10_000_000_000;
+            let swap_amount = zap_in_swap_amount(&pool, &fee, MIN_SQRT_PRICE, MAX_SQRT_PRICE, direction, amount_in, proxy_fee_bps).unwrap();
+            assert!(swap_amount > 0 && swap_amount < amount_in);
+
+            // the pool only sees the swap amount net of the proxy fee
+            let pool_amount = swap_amount - swap_amount * proxy_fee_bps as u64 / 10_000;
+            let amount_out = quote(&pool, &fee, direction, pool_amount).unwrap().amount_out;
+            let next_sqrt_price = MeteoraDammV2PoolSwapParams::next_sqrt_price(direction, pool.liquidity, pool.sqrt_price, pool_amount).unwrap();
+            let remaining = amount_in - swap_amount;
+
+            let (amount_a, amount_b) = if direction { (remaining, amount_out) } else { (amount_out, remaining) };
+            let liquidity_a = liquidity_from_amount_a(amount_a, next_sqrt_price, MAX_SQRT_PRICE);
+            let liquidity_b = liquidity_from_amount_b(amount_b, next_sqrt_price, MIN_SQRT_PRICE);
+
+            // within 0.01% of each other
+            assert!(liquidity_a.abs_diff(liquidity_b) <= liquidity_a.max(liquidity_b) / 10_000);
+        }
+    }
+
+    #[test]
+    fn next_sqrt_price_overflow_is_not_saturated() {
+        assert_eq!(MeteoraDammV2PoolSwapParams::next_sqrt_price(false, 1, MAX_SQRT_PRICE, u64::MAX), None);
+
+        let pool = MeteoraDammV2Pool::new(EXPECT_TOTAL_BYTES, 1, MAX_SQRT_PRICE);
+        assert_eq!(zap_in_swap_amount(&pool, &fee(0), MIN_SQRT_PRICE, MAX_SQRT_PRICE, false, u64::MAX, 0), None);
+    }
+
+    #[test]
+    fn liquidity_is_unbounded_at_the_edge_of_the_range() {
+        assert_eq!(liquidity_from_amount_a(1_000, MAX_SQRT_PRICE, MAX_SQRT_PRICE), u128::MAX);
+        assert_eq!(liquidity_from_amount_b(1_000, MIN_SQRT_PRICE, MIN_SQRT_PRICE), u128::MAX);
+        assert!(liquidity_from_amount_a(2_000, SQRT_PRICE_ONE, MAX_SQRT_PRICE) > liquidity_from_amount_a(1_000, SQRT_PRICE_ONE, MAX_SQRT_PRICE));
+        assert!(liquidity_from_amount_b(2_000, SQRT_PRICE_ONE, MIN_SQRT_PRICE) > liquidity_from_amount_b(1_000, SQRT_PRICE_ONE, MIN_SQRT_PRICE));
+    }
+
+    fn vesting(cliff_point: Option<u64>, period_frequency: u64, number_of_period: u16) -> VestingParameters {
+        VestingParameters {
+            cliff_point,
+            period_frequency,
+            cliff_unlock_liquidity: 1_000,
+            liquidity_per_period: 1_000,
+            number_of_period,
+        }
+    }
+
+    #[test]
+    fn vesting_accepts_a_schedule_within_the_limits() {
+        assert!(vesting(Some(1_100), 100, 10).validate(1_000, MAX_VESTING_SLOT_DURATION).is_ok());
+        assert!(vesting(None, 100, 10).validate(1_000, MAX_VESTING_SLOT_DURATION).is_ok());
+    }
+
+    #[test]
+    fn vesting_rejects_a_cliff_in_the_past() {
+        assert_eq!(
+            vesting(Some(999), 100, 10).validate(1_000, MAX_VESTING_SLOT_DURATION),
+            Err("cliff point is in the past"),
+        );
+    }
+
+    #[test]
+    fn vesting_rejects_durations_over_the_maximum() {
+        assert_eq!(
+            vesting(Some(1_000), MAX_VESTING_TIME_DURATION, 2).validate(1_000, MAX_VESTING_TIME_DURATION),
+            Err("vesting duration exceeds the maximum"),
+        );
+        assert_eq!(
+            vesting(Some(1_000 + MAX_VESTING_TIME_DURATION + 1), 0, 0).validate(1_000, MAX_VESTING_TIME_DURATION),
+            Err("vesting duration exceeds the maximum"),
+        );
+    }
+
+    fn customizable_pool(sqrt_min_price: u128, sqrt_max_price: u128, sqrt_price: u128) -> InitializeCustomizablePoolParameters {
+        InitializeCustomizablePoolParameters {
+            pool_fees: PoolFeeParameters {
+                base_fee: BaseFeeParameters {
+                    cliff_fee_numerator: FEE_NUMERATOR,
+                    number_of_period: 0,
+                    period_frequency: 0,
+                    reduction_factor: 0,
+                    fee_scheduler_mode: 0,
+                },
+                padding: [0; 3],
+                dynamic_fee: None,
+            },
+            sqrt_min_price,
+            sqrt_max_price,
+            has_alpha_vault: false,
+            liquidity: 1_000_000 << 64,
+            sqrt_price,
+            activation_type: 1,
+            collect_fee_mode: COLLECT_FEE_MODE_ONLY_B,
+            activation_point: None,
+        }
+    }
+
+    #[test]
+    fn customizable_pool_accepts_the_full_range() {
+        assert_eq!(customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, SQRT_PRICE_ONE).validate(), Ok(()));
+        assert_eq!(customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, MIN_SQRT_PRICE).validate(), Ok(()));
+        assert_eq!(customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, MAX_SQRT_PRICE).validate(), Ok(()));
+    }
+
+    #[test]
+    fn customizable_pool_rejects_bad_price_ranges() {
+        assert_eq!(
+            customizable_pool(MIN_SQRT_PRICE - 1, MAX_SQRT_PRICE, SQRT_PRICE_ONE).validate(),
+            Err("sqrt price range is outside of the supported bounds"),
+        );
+        assert_eq!(
+            customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE + 1, SQRT_PRICE_ONE).validate(),
+            Err("sqrt price range is outside of the supported bounds"),
+        );
+        assert_eq!(
+            customizable_pool(SQRT_PRICE_ONE, SQRT_PRICE_ONE, SQRT_PRICE_ONE).validate(),
+            Err("sqrt min price must be lower than sqrt max price"),
+        );
+        assert_eq!(
+            customizable_pool(SQRT_PRICE_ONE, MAX_SQRT_PRICE, SQRT_PRICE_ONE - 1).validate(),
+            Err("sqrt price is outside of the price range"),
+        );
+        assert_eq!(
+            customizable_pool(MIN_SQRT_PRICE, SQRT_PRICE_ONE, SQRT_PRICE_ONE + 1).validate(),
+            Err("sqrt price is outside of the price range"),
+        );
+    }
+
+    #[test]
+    fn customizable_pool_rejects_empty_liquidity_and_unknown_modes() {
+        let mut params = customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, SQRT_PRICE_ONE);
+        params.liquidity = 0;
+        assert_eq!(params.validate(), Err("liquidity must be greater than zero"));
+
+        let mut params = customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, SQRT_PRICE_ONE);
+        params.activation_type = 2;
+        assert_eq!(params.validate(), Err("unknown activation type"));
+
+        let mut params = customizable_pool(MIN_SQRT_PRICE, MAX_SQRT_PRICE, SQRT_PRICE_ONE);
+        params.collect_fee_mode = 2;
+        assert_eq!(params.validate(), Err("unknown collect fee mode"));
+    }
+}
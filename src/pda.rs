@@ -0,0 +1,226 @@
+use solana_program::{bpf_loader_upgradeable, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+/// Seeds used by the Meteora DAMM v2 program, mirrored from `examples/constants.rs`.
+pub mod seeds {
+    pub const CUSTOMIZABLE_POOL_PREFIX: &[u8] = b"cpool";
+    pub const POOL_PREFIX: &[u8] = b"pool";
+    pub const TOKEN_VAULT_PREFIX: &[u8] = b"token_vault";
+    pub const POSITION_PREFIX: &[u8] = b"position";
+    pub const POSITION_NFT_ACCOUNT_PREFIX: &[u8] = b"position_nft_account";
+    pub const REWARD_VAULT_PREFIX: &[u8] = b"reward_vault";
+    pub const EVENT_AUTHORITY: &[u8] = b"__event_authority";
+}
+
+// The pool authority is a constant address specified in the IDL, not a PDA.
+// HLnpSz9h2S4hiLQ43rnSD9XkcUThA7B8hQMKmDaiTLcC
+pub const POOL_AUTHORITY: Pubkey = Pubkey::new_from_array([242, 204, 213, 53, 172, 165, 241, 115, 106, 200, 34, 221, 7, 115, 228, 217, 47, 189, 138, 89, 178, 148, 3, 80, 2, 149, 169, 1, 28, 115, 169, 229]);
+
+// Seed of the treasury PDA owned by this program. It signs CPIs for
+// protocol-owned token accounts and positions.
+pub const TREASURY_PREFIX: &[u8] = b"treasury";
+
+// Seed of the config PDA holding the proxy settings. It also owns the fee vaults.
+pub const CONFIG_PREFIX: &[u8] = b"config";
+
+// Seed of the per-pool allowlist markers owned by this program.
+pub const ALLOWED_POOL_PREFIX: &[u8] = b"allowed_pool";
+
+// nft_mint bytes range inside a Meteora `Position` account
+pub const POSITION_NFT_MINT_BEGIN: usize = 32 + 8;
+pub const POSITION_NFT_MINT_END: usize = POSITION_NFT_MINT_BEGIN + 32;
+
+pub struct Pda;
+
+impl Pda {
+    /// Derives the treasury authority of this program.
+    pub fn treasury(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TREASURY_PREFIX], program_id)
+    }
+
+    /// Derives the proxy config of this program.
+    pub fn config(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_PREFIX], program_id)
+    }
+
+    /// Derives the allowlist marker of a Meteora `pool`.
+    pub fn allowed_pool(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ALLOWED_POOL_PREFIX, pool.as_ref()], program_id)
+    }
+
+    /// Derives the `ProgramData` account of this program under the upgradeable loader.
+    pub fn program_data(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID).0
+    }
+
+    /// The fee vault of `token_mint`: the associated token account of the config PDA.
+    pub fn fee_vault(config: &Pubkey, token_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(config, token_mint, token_program)
+    }
+
+    /// Derives a pool created from a static or dynamic `config`.
+    pub fn pool(config: &Pubkey, token_a_mint: &Pubkey, token_b_mint: &Pubkey, meteora_program_id: &Pubkey) -> Pubkey {
+        let (max_mint, min_mint) = Self::sort_mints(token_a_mint, token_b_mint);
+
+        Pubkey::find_program_address(
+            &[seeds::POOL_PREFIX, config.as_ref(), max_mint.as_ref(), min_mint.as_ref()],
+            meteora_program_id,
+        ).0
+    }
+
+    /// Derives a customizable pool, which is keyed by its mints instead of a config.
+    pub fn customizable_pool(token_a_mint: &Pubkey, token_b_mint: &Pubkey, meteora_program_id: &Pubkey) -> Pubkey {
+        let (max_mint, min_mint) = Self::sort_mints(token_a_mint, token_b_mint);
+
+        Pubkey::find_program_address(
+            &[seeds::CUSTOMIZABLE_POOL_PREFIX, max_mint.as_ref(), min_mint.as_ref()],
+            meteora_program_id,
+        ).0
+    }
+
+    /// cp-amm orders the mints as (max, min) in the pool seeds.
+    fn sort_mints<'a>(token_a_mint: &'a Pubkey, token_b_mint: &'a Pubkey) -> (&'a Pubkey, &'a Pubkey) {
+        if token_a_mint > token_b_mint {
+            (token_a_mint, token_b_mint)
+        } else {
+            (token_b_mint, token_a_mint)
+        }
+    }
+
+    /// Derives the vault holding `token_mint` for `pool`.
+    pub fn token_vault(token_mint: &Pubkey, pool: &Pubkey, meteora_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[seeds::TOKEN_VAULT_PREFIX, token_mint.as_ref(), pool.as_ref()],
+            meteora_program_id,
+        ).0
+    }
+
+    /// Derives the vault holding the farming reward of slot `reward_index`.
+    pub fn reward_vault(pool: &Pubkey, reward_index: u8, meteora_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[seeds::REWARD_VAULT_PREFIX, pool.as_ref(), &[reward_index]],
+            meteora_program_id,
+        ).0
+    }
+
+    /// Derives the anchor event authority of the Meteora program.
+    pub fn event_authority(meteora_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[seeds::EVENT_AUTHORITY], meteora_program_id).0
+    }
+
+    /// Derives the `position` PDA owned by the given position NFT mint.
+    pub fn position(position_nft_mint: &Pubkey, meteora_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[seeds::POSITION_PREFIX, position_nft_mint.as_ref()],
+            meteora_program_id,
+        ).0
+    }
+
+    /// Derives the token account that holds the position NFT.
+    pub fn position_nft_account(position_nft_mint: &Pubkey, meteora_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[seeds::POSITION_NFT_ACCOUNT_PREFIX, position_nft_mint.as_ref()],
+            meteora_program_id,
+        ).0
+    }
+
+    /// Reads the position NFT mint stored in a Meteora `Position` account.
+    pub fn position_nft_mint_from_data(position_data: &[u8]) -> Option<Pubkey> {
+        let bytes: [u8; 32] = position_data
+            .get(POSITION_NFT_MINT_BEGIN..POSITION_NFT_MINT_END)?
+            .try_into()
+            .ok()?;
+
+        Some(Pubkey::new_from_array(bytes))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn pool_authority_is_the_idl_address() {
+        assert_eq!(POOL_AUTHORITY, Pubkey::from_str("HLnpSz9h2S4hiLQ43rnSD9XkcUThA7B8hQMKmDaiTLcC").unwrap());
+    }
+
+    #[test]
+    fn pools_do_not_depend_on_the_mint_order() {
+        let meteora_program_id = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert_eq!(
+            Pda::pool(&config, &mint_a, &mint_b, &meteora_program_id),
+            Pda::pool(&config, &mint_b, &mint_a, &meteora_program_id),
+        );
+        assert_eq!(
+            Pda::customizable_pool(&mint_a, &mint_b, &meteora_program_id),
+            Pda::customizable_pool(&mint_b, &mint_a, &meteora_program_id),
+        );
+        assert_ne!(
+            Pda::pool(&config, &mint_a, &mint_b, &meteora_program_id),
+            Pda::customizable_pool(&mint_a, &mint_b, &meteora_program_id),
+        );
+    }
+
+    #[test]
+    fn pool_seeds_put_the_larger_mint_first() {
+        let meteora_program_id = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (max_mint, min_mint) = if mint_a > mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) };
+
+        assert_eq!(Pda::sort_mints(&min_mint, &max_mint), (&max_mint, &min_mint));
+
+        let (expected, _) = Pubkey::find_program_address(
+            &[seeds::POOL_PREFIX, config.as_ref(), max_mint.as_ref(), min_mint.as_ref()],
+            &meteora_program_id,
+        );
+        assert_eq!(Pda::pool(&config, &min_mint, &max_mint, &meteora_program_id), expected);
+    }
+
+    #[test]
+    fn program_pdas_are_distinct() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+
+        let (treasury, _) = Pda::treasury(&program_id);
+        let (config, _) = Pda::config(&program_id);
+        let (allowed_pool, _) = Pda::allowed_pool(&pool, &program_id);
+
+        assert_ne!(treasury, config);
+        assert_ne!(allowed_pool, config);
+        assert_ne!(allowed_pool, Pda::allowed_pool(&Pubkey::new_unique(), &program_id).0);
+    }
+
+    #[test]
+    fn fee_vault_is_the_config_associated_token_account() {
+        let config = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+
+        assert_eq!(
+            Pda::fee_vault(&config, &token_mint, &spl_token_2022::ID),
+            get_associated_token_address_with_program_id(&config, &token_mint, &spl_token_2022::ID),
+        );
+        assert_ne!(
+            Pda::fee_vault(&config, &token_mint, &spl_token_2022::ID),
+            Pda::fee_vault(&config, &token_mint, &spl_token::ID),
+        );
+    }
+
+    #[test]
+    fn position_nft_mint_is_read_after_the_discriminator_and_pool() {
+        let nft_mint = Pubkey::new_unique();
+        let mut position_data = vec![0u8; POSITION_NFT_MINT_END + 8];
+        position_data[POSITION_NFT_MINT_BEGIN..POSITION_NFT_MINT_END].copy_from_slice(nft_mint.as_ref());
+
+        assert_eq!(Pda::position_nft_mint_from_data(&position_data), Some(nft_mint));
+        assert_eq!(Pda::position_nft_mint_from_data(&position_data[..POSITION_NFT_MINT_END - 1]), None);
+    }
+}
@@ -0,0 +1,23 @@
+use solana_program::pubkey::Pubkey;
+
+// The DAMM v2 program every CPI is pinned to. Select the cluster with the `mainnet`,
+// `devnet` or `localnet` cargo feature. `devnet` is the default and gives way to an
+// explicitly selected `mainnet` or `localnet`.
+
+#[cfg(all(feature = "mainnet", feature = "localnet"))]
+compile_error!("enable only one of the `mainnet` or `localnet` features");
+
+#[cfg(not(any(feature = "mainnet", feature = "devnet", feature = "localnet")))]
+compile_error!("enable one of the `mainnet`, `devnet` or `localnet` features");
+
+// mainnet meteora program id  cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG
+#[cfg(feature = "mainnet")]
+pub const METEORA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([9, 45, 33, 53, 101, 122, 21, 156, 43, 135, 212, 182, 106, 112, 219, 142, 151, 82, 56, 159, 247, 106, 175, 32, 108, 237, 6, 58, 56, 249, 90, 237]);
+
+// devnet meteora program id  cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG
+#[cfg(all(feature = "devnet", not(feature = "mainnet"), not(feature = "localnet")))]
+pub const METEORA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([9, 45, 33, 53, 101, 122, 21, 156, 43, 135, 212, 182, 106, 112, 219, 142, 151, 82, 56, 159, 247, 106, 175, 32, 108, 237, 6, 58, 56, 249, 90, 237]);
+
+// localnet meteora program id, the cp-amm `declare_id!` loaded into the test validator
+#[cfg(feature = "localnet")]
+pub const METEORA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([9, 45, 33, 53, 101, 122, 21, 156, 43, 135, 212, 182, 106, 112, 219, 142, 151, 82, 56, 159, 247, 106, 175, 32, 108, 237, 6, 58, 56, 249, 90, 237]);
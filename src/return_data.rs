@@ -0,0 +1,117 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use solana_program::{
+    program::{get_return_data, set_return_data},
+    pubkey::Pubkey,
+};
+
+/// Published by every swap handler with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapResult {
+    // tokens that left the input account
+    pub amount_in: u64,
+    // tokens that arrived in the output account, after Token-2022 transfer fees
+    pub amount_out: u64,
+    // fee charged by the pool, in the token it collects fees in
+    pub fee: u64,
+    // pool sqrt price after the swap
+    pub sqrt_price: u128,
+}
+
+/// Published by every liquidity handler with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidityResult {
+    // token A deposited or withdrawn by the owner
+    pub token_a_amount: u64,
+    // token B deposited or withdrawn by the owner
+    pub token_b_amount: u64,
+    // change of the pool liquidity
+    pub liquidity_delta: u128,
+    // pool sqrt price after the operation
+    pub sqrt_price: u128,
+}
+
+/// Published by the routed swap handler with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RouteSwapResult {
+    // tokens that left the input account of the first hop
+    pub amount_in: u64,
+    // tokens that arrived in the output account of the last hop
+    pub amount_out: u64,
+    // realized amounts of every hop, in route order
+    pub hops: Vec<SwapResult>,
+}
+
+/// Published by the split swap handler with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SplitSwapResult {
+    // tokens that left the input account over all legs
+    pub amount_in: u64,
+    // tokens that arrived in the output account over all legs
+    pub amount_out: u64,
+    // realized amounts of every leg, in instruction order
+    pub legs: Vec<SwapResult>,
+}
+
+/// Published by the zap-out handler with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZapOutResult {
+    // tokens withdrawn from the position
+    pub liquidity: LiquidityResult,
+    // realized amounts of the swap back into the output token
+    pub swap: SwapResult,
+    // total output token received: withdrawn plus swapped
+    pub amount_out: u64,
+}
+
+/// Published by the read-only `Quote` instruction with `set_return_data`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteResult {
+    // tokens the swap would spend
+    pub amount_in: u64,
+    // tokens the swap would return, after the pool base fee
+    pub amount_out: u64,
+    // base fee the pool would charge, in the token it collects fees in
+    pub fee: u64,
+    // distance between the execution price and the spot price, in basis points
+    pub price_impact_bps: u64,
+    // pool sqrt price the quote was computed at
+    pub sqrt_price: u128,
+}
+
+/// Borsh encoding shared by the results the proxy returns to its caller.
+pub trait ReturnData: BorshSerialize + BorshDeserialize + Sized {
+
+    /// Sets the result as the return data of the current instruction.
+    fn publish(&self) {
+        set_return_data(&self.try_to_vec().unwrap());
+    }
+
+    /// Decodes raw return data bytes, e.g. from `simulateTransaction` or transaction metadata.
+    fn decode(data: &[u8]) -> Option<Self> {
+        Self::try_from_slice(data).ok()
+    }
+
+    /// Reads the result after a CPI into the proxy, from another on-chain program.
+    fn get(proxy_program_id: &Pubkey) -> Option<Self> {
+        let (program_id, data) = get_return_data()?;
+
+        if &program_id != proxy_program_id {
+            return None;
+        }
+
+        Self::decode(&data)
+    }
+}
+
+impl ReturnData for SwapResult {}
+
+impl ReturnData for LiquidityResult {}
+
+impl ReturnData for QuoteResult {}
+
+impl ReturnData for RouteSwapResult {}
+
+impl ReturnData for SplitSwapResult {}
+
+impl ReturnData for ZapOutResult {}
@@ -0,0 +1,239 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use solana_program::{
+    account_info::AccountInfo,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MeteoraProxyError;
+use crate::pda::Pda;
+use crate::program_id::METEORA_PROGRAM_ID;
+
+// 10% upper bound on the proxy fee
+pub const MAX_PROXY_FEE_BPS: u16 = 1_000;
+
+/// Program-wide settings, stored in the `config` PDA.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyConfig {
+    // the only signer allowed to update the config and collect fees
+    pub admin: Pubkey,
+    // admin proposed by `TransferAdmin`, default while none is pending
+    pub pending_admin: Pubkey,
+    // halts every non-admin instruction while set
+    pub paused: bool,
+    // share of every swap input skimmed into the fee vault
+    pub fee_bps: u16,
+    // owner of the token accounts collected fees are sent to
+    pub fee_recipient: Pubkey,
+    // DAMM v2 program the proxy may call, must agree with the cluster feature
+    pub meteora_program: Pubkey,
+    // bump of the config PDA
+    pub bump: u8,
+}
+
+/// Allowlist marker of one Meteora pool, stored in the `allowed_pool` PDA.
+///
+/// The proxy only swaps and moves liquidity in pools that have one.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllowedPool {
+    // the allowed Meteora pool
+    pub pool: Pubkey,
+    // bump of the marker PDA
+    pub bump: u8,
+}
+
+impl AllowedPool {
+
+    pub const LEN: usize = 32 + 1;
+}
+
+/// Fields changed by `UpdateConfig`; `None` keeps the current value.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfigUpdate {
+    pub paused: Option<bool>,
+    pub fee_bps: Option<u16>,
+    pub fee_recipient: Option<Pubkey>,
+    pub meteora_program: Option<Pubkey>,
+}
+
+impl ProxyConfig {
+
+    pub const LEN: usize = 32 + 32 + 1 + 2 + 32 + 32 + 1;
+
+    /// Reads the config after checking it is the initialized config PDA of this program.
+    pub fn load(program_id: &Pubkey, account: &AccountInfo) -> Result<Self, ProgramError> {
+        let (config, _) = Pda::config(program_id);
+
+        if account.key != &config || account.owner != program_id {
+            msg!("Account {} is not the proxy config", account.key);
+            return Err(MeteoraProxyError::InvalidProxyConfig.into());
+        }
+
+        let data = account.try_borrow_data()?;
+        Self::deserialize(&mut &data[..]).map_err(|_| MeteoraProxyError::InvalidProxyConfig.into())
+    }
+
+    /// Reads the config and fails when the proxy is paused or may not call the pinned
+    /// Meteora program. Gate of every non-admin instruction.
+    pub fn load_active(program_id: &Pubkey, account: &AccountInfo) -> Result<Self, ProgramError> {
+        let config = Self::load(program_id, account)?;
+
+        if config.paused {
+            msg!("The proxy is paused");
+            return Err(MeteoraProxyError::Paused.into());
+        }
+
+        if config.meteora_program != METEORA_PROGRAM_ID {
+            msg!("Config allows Meteora program {}, this build is pinned to {}", config.meteora_program, METEORA_PROGRAM_ID);
+            return Err(MeteoraProxyError::InvalidMeteoraProgram.into());
+        }
+
+        Ok(config)
+    }
+
+    /// Checks that `admin` is the config admin and signed the transaction.
+    pub fn check_admin(&self, admin: &AccountInfo) -> Result<(), ProgramError> {
+        if !admin.is_signer || admin.key != &self.admin {
+            msg!("{} is not the proxy admin", admin.key);
+            return Err(MeteoraProxyError::Unauthorized.into());
+        }
+        Ok(())
+    }
+
+    /// Applies the fields set in `update`.
+    ///
+    /// The Meteora program can only be set back to the one pinned by the cluster feature,
+    /// any other key would make `load_active` fail every non-admin instruction.
+    pub fn apply(&mut self, update: ConfigUpdate) -> Result<(), ProgramError> {
+        if let Some(fee_bps) = update.fee_bps {
+            Self::validate_fee_bps(fee_bps)?;
+            self.fee_bps = fee_bps;
+        }
+
+        if let Some(meteora_program) = update.meteora_program {
+            if meteora_program != METEORA_PROGRAM_ID {
+                msg!("Meteora program {} is not the pinned {}", meteora_program, METEORA_PROGRAM_ID);
+                return Err(MeteoraProxyError::InvalidMeteoraProgram.into());
+            }
+        }
+
+        self.paused = update.paused.unwrap_or(self.paused);
+        self.fee_recipient = update.fee_recipient.unwrap_or(self.fee_recipient);
+        self.meteora_program = update.meteora_program.unwrap_or(self.meteora_program);
+        Ok(())
+    }
+
+    /// Writes the config back into its account.
+    pub fn save(&self, account: &AccountInfo) -> Result<(), ProgramError> {
+        let mut data = account.try_borrow_mut_data()?;
+        self.serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    /// Proxy fee owed on a swap of `amount`, rounded down.
+    pub fn fee_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Checks the fee settings before they are stored.
+    pub fn validate_fee_bps(fee_bps: u16) -> Result<(), ProgramError> {
+        if fee_bps > MAX_PROXY_FEE_BPS {
+            msg!("Proxy fee {} bps is above {}", fee_bps, MAX_PROXY_FEE_BPS);
+            return Err(MeteoraProxyError::InvalidFeeBps.into());
+        }
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn config(fee_bps: u16) -> ProxyConfig {
+        ProxyConfig {
+            admin: Pubkey::new_unique(),
+            pending_admin: Pubkey::default(),
+            paused: false,
+            fee_bps,
+            fee_recipient: Pubkey::new_unique(),
+            meteora_program: METEORA_PROGRAM_ID,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn len_matches_the_serialized_size() {
+        assert_eq!(borsh::to_vec(&config(30)).unwrap().len(), ProxyConfig::LEN);
+        assert_eq!(borsh::to_vec(&AllowedPool { pool: Pubkey::new_unique(), bump: 255 }).unwrap().len(), AllowedPool::LEN);
+    }
+
+    #[test]
+    fn fee_is_rounded_down() {
+        assert_eq!(config(30).fee_on(1_000_000), 3_000);
+        assert_eq!(config(30).fee_on(333), 0);
+        assert_eq!(config(0).fee_on(u64::MAX), 0);
+        assert_eq!(config(MAX_PROXY_FEE_BPS).fee_on(u64::MAX), u64::MAX / 10);
+    }
+
+    #[test]
+    fn fee_bps_is_capped() {
+        assert!(ProxyConfig::validate_fee_bps(MAX_PROXY_FEE_BPS).is_ok());
+        assert_eq!(
+            ProxyConfig::validate_fee_bps(MAX_PROXY_FEE_BPS + 1),
+            Err(MeteoraProxyError::InvalidFeeBps.into()),
+        );
+    }
+
+    #[test]
+    fn apply_only_changes_the_fields_that_are_set() {
+        let mut proxy_config = config(30);
+        let before = proxy_config;
+        let fee_recipient = Pubkey::new_unique();
+
+        proxy_config.apply(ConfigUpdate::default()).unwrap();
+        assert_eq!(proxy_config, before);
+
+        proxy_config.apply(ConfigUpdate {
+            paused: Some(true),
+            fee_recipient: Some(fee_recipient),
+            ..ConfigUpdate::default()
+        }).unwrap();
+
+        assert!(proxy_config.paused);
+        assert_eq!(proxy_config.fee_recipient, fee_recipient);
+        assert_eq!(proxy_config.fee_bps, before.fee_bps);
+        assert_eq!(proxy_config.admin, before.admin);
+    }
+
+    #[test]
+    fn apply_rejects_invalid_updates_without_changing_the_config() {
+        let mut proxy_config = config(30);
+        let before = proxy_config;
+
+        let too_high_fee = ConfigUpdate {
+            paused: Some(true),
+            fee_bps: Some(MAX_PROXY_FEE_BPS + 1),
+            ..ConfigUpdate::default()
+        };
+        assert_eq!(proxy_config.apply(too_high_fee), Err(MeteoraProxyError::InvalidFeeBps.into()));
+        assert_eq!(proxy_config, before);
+
+        let other_program = ConfigUpdate {
+            meteora_program: Some(Pubkey::new_unique()),
+            ..ConfigUpdate::default()
+        };
+        assert_eq!(proxy_config.apply(other_program), Err(MeteoraProxyError::InvalidMeteoraProgram.into()));
+        assert_eq!(proxy_config, before);
+
+        let pinned_program = ConfigUpdate {
+            meteora_program: Some(METEORA_PROGRAM_ID),
+            ..ConfigUpdate::default()
+        };
+        assert_eq!(proxy_config.apply(pinned_program), Ok(()));
+    }
+}
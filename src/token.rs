@@ -0,0 +1,98 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MeteoraProxyError;
+
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    instruction::transfer_checked,
+    state::{Account, Mint},
+};
+
+pub struct TokenAccount;
+
+impl TokenAccount {
+    /// Reads the token balance of an SPL Token or SPL Token 2022 account.
+    ///
+    /// Both programs share the same base account layout, so the Token 2022
+    /// parser also handles legacy SPL Token accounts.
+    pub fn amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+        Ok(Self::unpack(account)?.amount)
+    }
+
+    /// Returns how many tokens arrived in `account` since the `before` snapshot.
+    pub fn received_since(account: &AccountInfo, before: u64) -> Result<u64, ProgramError> {
+        let after = Self::amount(account)?;
+
+        Ok(after.saturating_sub(before))
+    }
+
+    /// Returns how many tokens left `account` since the `before` snapshot.
+    pub fn spent_since(account: &AccountInfo, before: u64) -> Result<u64, ProgramError> {
+        let after = Self::amount(account)?;
+
+        Ok(before.saturating_sub(after))
+    }
+
+    /// Reads the owner of an SPL Token or SPL Token 2022 account.
+    pub fn owner(account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        Ok(Self::unpack(account)?.owner)
+    }
+
+    /// Moves `amount` with `transfer_checked`, which both token programs support.
+    ///
+    /// `signer_seeds` is empty when `authority` signed the transaction.
+    pub fn transfer<'info>(
+        token_program: &AccountInfo<'info>,
+        source: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let decimals = {
+            let data = mint.try_borrow_data()?;
+            StateWithExtensions::<Mint>::unpack(&data)
+                .map_err(|_| MeteoraProxyError::InvalidTokenAccount)?
+                .base
+                .decimals
+        };
+
+        let instruction = transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &instruction,
+            &[source.clone(), mint.clone(), destination.clone(), authority.clone(), token_program.clone()],
+            signer_seeds,
+        )
+    }
+
+    fn unpack(account: &AccountInfo) -> Result<Account, ProgramError> {
+        if account.owner != &spl_token::ID && account.owner != &spl_token_2022::ID {
+            msg!("Account {} is not owned by a token program", account.key);
+            return Err(MeteoraProxyError::InvalidTokenAccount.into());
+        }
+
+        let data = account.try_borrow_data()?;
+        let state = StateWithExtensions::<Account>::unpack(&data)
+            .map_err(|_| MeteoraProxyError::InvalidTokenAccount)?;
+
+        Ok(state.base)
+    }
+}